@@ -19,17 +19,67 @@ Part 2: Restrict to moving whole files.
 
 Approach: same as above, but only swap if the whole file
 on the right fits in the empty space on the left
+
+Rescanning the blocks from the left for every file is slow on big disks, so by default
+whole files are moved on a model of the disk as file and gap spans instead (see spans.rs).
+The block-level version is still available, and the tests check that both agree on
+random disk maps:
+$ cargo run input.txt --blocks
+$ cargo test
+
+To watch the files move (see visual.rs):
+$ cargo run input.txt --visual
 */
 use std::io;
 
-use utils::read_from_args;
+use utils::{has_flag, read_from_args};
+
+mod spans;
+mod visual;
 
 fn main() -> io::Result<()> {
     let disk_map = read_from_args()?;
+    let disk_map = disk_map.trim();
+
+    if !has_flag("blocks") {
+        let mut disk = spans::Disk::parse(disk_map);
+        if has_flag("visual") {
+            visual::compact(&mut disk);
+            visual::report(&disk.to_blocks());
+            return Ok(());
+        }
+        disk.compact();
+        let checksum = disk.checksum();
+        dbg!(checksum);
+        return Ok(());
+    }
 
-    // O(n)
     #[allow(unused_mut)]
-    let mut file_blocks: Vec<isize> = disk_map.chars().enumerate().map(
+    let mut file_blocks = parse_blocks(disk_map);
+    // dbg!(&file_blocks);
+
+    // selection "compact" O(n^2)
+    // selection_compact(&mut file_blocks);
+    // quick "compact" O(nlogn)
+    // quick_compact(&mut file_blocks);
+    // contiguous quick compact O(nlogn)
+    contiguous_quick_compact(&mut file_blocks);
+    // dbg!(&file_blocks);
+
+    if has_flag("visual") {
+        println!("{}", visual::draw(&file_blocks));
+        visual::report(&file_blocks);
+        return Ok(());
+    }
+
+    let checksum = checksum(&file_blocks);
+    dbg!(checksum);
+    Ok(())
+}
+
+fn parse_blocks(disk_map: &str) -> Vec<isize> {
+    // O(n)
+    disk_map.chars().enumerate().map(
         |(i, number)| {
             let length = number.to_digit(10).unwrap();
             if i % 2 == 0 {
@@ -40,23 +90,14 @@ fn main() -> io::Result<()> {
                 vec![-1; length as usize]
             }
         }
-    ).flatten().collect();
-    // dbg!(&file_blocks);
-
-    // selection "compact" O(n^2)
-    // selection_compact(&mut file_blocks);
-    // quick "compact" O(nlogn)
-    // quick_compact(&mut file_blocks);
-    // contiguous quick compact O(nlogn)
-    contiguous_quick_compact(&mut file_blocks);
-    // dbg!(&file_blocks);
+    ).flatten().collect()
+}
 
+fn checksum(file_blocks: &[isize]) -> usize {
     // O(n)
-    let checksum: usize = file_blocks.iter().enumerate().fold(0, 
+    file_blocks.iter().enumerate().fold(0, 
         |checksum, (position, id)| if *id >= 0 {checksum + position * (*id) as usize} else {checksum}
-    );
-    dbg!(checksum);
-    Ok(())
+    )
 }
 
 #[allow(dead_code)]
@@ -219,4 +260,32 @@ fn print_sequence(sequence: &Vec<isize>, left: usize, right: usize) {
         left=left+1,
         right=right.saturating_sub(left)
     );
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use utils::XorShift;
+
+    use super::*;
+
+    #[test]
+    fn spans_and_blocks_compact_the_same() {
+        let mut random = XorShift::new(0x9e3779b97f4a7c15);
+        for _ in 0..1000 {
+            let length = 1 + random.below(60);
+            // files take 1 to 9 blocks, gaps 0 to 9
+            let disk_map: String = (0..length).map(|i| {
+                let digit = if i % 2 == 0 { 1 + random.below(9) } else { random.below(10) };
+                char::from_digit(digit as u32, 10).unwrap()
+            }).collect();
+
+            let mut blocks = parse_blocks(&disk_map);
+            contiguous_quick_compact(&mut blocks);
+            let mut disk = spans::Disk::parse(&disk_map);
+            disk.compact();
+
+            assert_eq!(disk.to_blocks(), blocks, "layouts differ on {disk_map}");
+            assert_eq!(disk.checksum(), checksum(&blocks), "checksums differ on {disk_map}");
+        }
+    }
+}
@@ -0,0 +1,99 @@
+/*
+Span model of the disk, for whole-file compaction in O(n log n).
+
+Instead of one entry per block, the disk is a list of files (position, length) indexed
+by id, plus the free gaps kept in one min-heap of positions per gap size (1 to 9).
+The leftmost gap a file fits in is the smallest head among the heaps of its size and
+above, so moving a file costs at most 9 heap operations.
+If the gap is bigger than the file, what is left goes back into the heap of its new size.
+
+Files are moved in decreasing id order and only ever to the left, so the space a file
+leaves behind is to the right of every file still to move and is never reused.
+*/
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub position: usize,
+    pub length: usize
+}
+
+pub struct Disk {
+    // indexed by file id
+    pub files: Vec<Span>,
+    // number of blocks, free or not
+    pub size: usize,
+    // positions of the gaps of each size, leftmost first
+    gaps: [BinaryHeap<Reverse<usize>>; 10]
+}
+
+impl Disk {
+    pub fn parse(disk_map: &str) -> Disk {
+        let mut disk = Disk {
+            files: Vec::new(),
+            size: 0,
+            gaps: std::array::from_fn(|_| BinaryHeap::new())
+        };
+        for (i, number) in disk_map.chars().enumerate() {
+            let length = number.to_digit(10).unwrap() as usize;
+            if i % 2 == 0 {
+                disk.files.push(Span { position: disk.size, length });
+            } else if length > 0 {
+                disk.gaps[length].push(Reverse(disk.size));
+            }
+            disk.size += length;
+        }
+        disk
+    }
+
+    /// Moves every file, highest id first, to the leftmost gap that fits it.
+    pub fn compact(&mut self) {
+        for id in (0..self.files.len()).rev() {
+            self.move_file(id);
+        }
+    }
+
+    /// Moves a file to the leftmost gap that fits it, if that is to the left of the file.
+    /// Returns the span the file was moved from.
+    pub fn move_file(&mut self, id: usize) -> Option<Span> {
+        let file = self.files[id];
+        if file.length == 0 {
+            return None;
+        }
+        let (position, size) = (file.length..self.gaps.len())
+            .filter_map(|size| self.gaps[size].peek().map(|&Reverse(position)| (position, size)))
+            .min()?;
+        if position > file.position {
+            return None;
+        }
+
+        self.gaps[size].pop();
+        if size > file.length {
+            self.gaps[size - file.length].push(Reverse(position + file.length));
+        }
+        self.files[id].position = position;
+        Some(file)
+    }
+
+    /// Number of gaps of each size still available to move files into.
+    pub fn gap_counts(&self) -> [usize; 10] {
+        std::array::from_fn(|size| self.gaps[size].len())
+    }
+
+    pub fn checksum(&self) -> usize {
+        self.files.iter().enumerate().map(|(id, file)| {
+            // id * (position + ... + position + length - 1)
+            id * (file.length * file.position + file.length * file.length.saturating_sub(1) / 2)
+        }).sum()
+    }
+
+    /// The block-level layout, with -1 for free blocks, as used by the block-level compaction.
+    pub fn to_blocks(&self) -> Vec<isize> {
+        let mut blocks = vec![-1; self.size];
+        for (id, file) in self.files.iter().enumerate() {
+            blocks[file.position..file.position + file.length].fill(id as isize);
+        }
+        blocks
+    }
+}
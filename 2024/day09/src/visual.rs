@@ -0,0 +1,69 @@
+/*
+Visual mode, to debug compaction strategies:
+
+$ cargo run input.txt --visual [--blocks]
+
+With spans, every file move is logged with the span it moved from and to and the
+gaps left of each size, followed by the disk layout. Blocks are drawn with the last
+digit of their file id, coloured by id, and free blocks as dots.
+Either way it ends with a fragmentation report of the compacted disk.
+*/
+use crate::checksum;
+use crate::spans::{Disk, Span};
+
+pub fn draw(blocks: &[isize]) -> String {
+    let mut layout = String::new();
+    for &id in blocks {
+        if id < 0 {
+            layout.push('.');
+        } else {
+            // spread consecutive ids over the colour cube, skipping black and white
+            let color = 17 + (id as usize * 47) % 214;
+            layout.push_str(&format!("\x1b[38;5;{color}m{}\x1b[0m", id % 10));
+        }
+    }
+    layout
+}
+
+pub fn compact(disk: &mut Disk) {
+    println!("{}", draw(&disk.to_blocks()));
+    for id in (0..disk.files.len()).rev() {
+        let Some(from) = disk.move_file(id) else {
+            continue;
+        };
+        let to = disk.files[id];
+        let gaps: Vec<String> = disk.gap_counts().iter().enumerate().skip(1)
+            .map(|(size, count)| format!("{size}:{count}"))
+            .collect();
+        println!(
+            "file {id}: {} -> {}, gaps left by size {}",
+            format_span(from), format_span(to), gaps.join(" ")
+        );
+        println!("{}", draw(&disk.to_blocks()));
+    }
+}
+
+fn format_span(span: Span) -> String {
+    format!("{}..{}", span.position, span.position + span.length)
+}
+
+/// Gaps are the free runs before the last file block; free space after it is counted apart.
+pub fn report(blocks: &[isize]) {
+    let end = blocks.iter().rposition(|&id| id >= 0).map_or(0, |last| last + 1);
+    let mut gaps: Vec<usize> = Vec::new();
+    let mut run = 0;
+    for &id in &blocks[..end] {
+        if id < 0 {
+            run += 1;
+        } else if run > 0 {
+            gaps.push(run);
+            run = 0;
+        }
+    }
+
+    println!("gaps: {}", gaps.len());
+    println!("largest gap: {}", gaps.iter().max().unwrap_or(&0));
+    println!("free blocks in gaps: {}", gaps.iter().sum::<usize>());
+    println!("free blocks at the end: {}", blocks.len() - end);
+    println!("checksum: {}", checksum(blocks));
+}
@@ -7,28 +7,64 @@ What will the safety factor be in 100 seconds?
 
 Approach: each robot position will be given by
 
-X = X0 + Vx*100 (mod H)
-Y = Y0 + Vy*100 (mod W)
+X = X0 + Vx*100 (mod W)
+Y = Y0 + Vy*100 (mod H)
+
+where W and H are the board width and height (101x103, or 11x7 for the example).
+When the board has an even side there is no middle row/column, so the halves
+are split as X < W/2 and X >= (W+1)/2 (same for Y).
 
 Part 2: how long until robots line up in a christmas tree?
-Easiest way to do 
-$ cargo run input.txt > output.txt
-$ grep "############" output.txt --context=30
+Approach: when the tree shows up the robots are clustered together, so the
+variance of their positions drops. Each coordinate evolves independently and
+is periodic (period W for X, H for Y), so we find the time offset with the
+smallest X variance within 0..W and the one with the smallest Y variance within
+0..H, then combine both with the chinese remainder theorem:
+
+t = tx (mod W)
+t = ty (mod H)
+
+which has one solution below lcm(W, H) when tx = ty (mod gcd(W, H)), and none otherwise.
+
+Without --width and --height the board size is inferred from the furthest robot, which is
+only a best guess: if no robot reaches the last row or column the board comes out too small.
 */
 use std::{collections::HashMap, io};
 
-use utils::read_from_args;
+use utils::{get_arg, read_from_args};
 
 fn main() -> io::Result<()> {
     let input = read_from_args()?;
-    // inverting the coordinates because it's easier
-    // width -> height
-    // height -> width
-    let height: i32 = 101;
-    let width: i32 = 103;
-    let time = 100;
-
-    let mut initial_positions: Vec<[i32; 2]> = Vec::new();
+    let (initial_positions, velocities) = parse_input(&input);
+
+    // the board is 101x103 for the real input and 11x7 for the example,
+    // so by default infer it from the furthest robot
+    let width: i32 = get_arg("width")
+        .unwrap_or_else(|| initial_positions.iter().map(|p| p[0]).max().unwrap() + 1);
+    let height: i32 = get_arg("height")
+        .unwrap_or_else(|| initial_positions.iter().map(|p| p[1]).max().unwrap() + 1);
+    let time: i32 = get_arg("time").unwrap_or(100);
+
+    let positions = update_robots(&initial_positions, &velocities, time, width, height);
+    
+    let counts = count_robots(&positions);
+    let safety_factor = compute_safety_factor(counts, width, height);
+    dbg!(safety_factor);
+
+    match find_tree_time(&initial_positions, &velocities, width, height) {
+        Some(tree_time) => {
+            dbg!(tree_time);
+            let positions = update_robots(&initial_positions, &velocities, tree_time, width, height);
+            print_robots(&positions, width, height);
+        },
+        None => println!("No unique tree time: the X and Y variance minima disagree modulo gcd({width}, {height}).")
+    }
+
+    Ok(())
+}
+
+fn parse_input(input: &str) -> (Vec<[i32; 2]>, Vec<[i32; 2]>) {
+    let mut positions: Vec<[i32; 2]> = Vec::new();
     let mut velocities: Vec<[i32; 2]> = Vec::new();
 
     for line in input.lines() {
@@ -42,58 +78,73 @@ fn main() -> io::Result<()> {
         let position: [i32; 2] = [position_raw.0.parse().unwrap(), position_raw.1.parse().unwrap()];
         let velocity: [i32; 2] = [velocity_raw.0.parse().unwrap(), velocity_raw.1.parse().unwrap()];
 
-        initial_positions.push(position);
+        positions.push(position);
         velocities.push(velocity);
     }
-
-    let positions = update_robots(&initial_positions, &velocities, time, height, width);
-    
-    let counts = count_robots(&positions);
-    let safety_factor = compute_safety_factor(counts, height, width);
-    dbg!(safety_factor);
-
-    // search for christmas tree...
-    for t in 0..10000 {
-        let positions = update_robots(&initial_positions, &velocities, t, height, width);
-        println!("{t}");
-        print_robots(&positions, height, width);
-    }
-
-    Ok(())
+    (positions, velocities)
 }
 
-fn update_robots(positions: &Vec<[i32; 2]>, velocities: &Vec<[i32; 2]>, time: i32, height: i32, width: i32) -> Vec<[i32; 2]> {
+fn update_robots(positions: &Vec<[i32; 2]>, velocities: &Vec<[i32; 2]>, time: i32, width: i32, height: i32) -> Vec<[i32; 2]> {
     positions.iter().enumerate().map(|(i, position)| {
         let velocity = velocities[i];
         [
-            (position[0] + velocity[0] * time).rem_euclid(height),
-            (position[1] + velocity[1] * time).rem_euclid(width),
+            (position[0] + velocity[0] * time).rem_euclid(width),
+            (position[1] + velocity[1] * time).rem_euclid(height),
         ]
     }).collect()
 }
 
-fn compute_safety_factor(counts: HashMap<(i32, i32), usize>, height: i32, width: i32) -> usize {
+fn find_tree_time(positions: &Vec<[i32; 2]>, velocities: &Vec<[i32; 2]>, width: i32, height: i32) -> Option<i32> {
+    let min_variance_time = |axis: usize, period: i32| {
+        (0..period)
+            .map(|t| (t, variance(&update_robots(positions, velocities, t, width, height), axis)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap().0
+    };
+    let tx = min_variance_time(0, width);
+    let ty = min_variance_time(1, height);
+
+    // chinese remainder theorem: walk t = tx (mod W) until t = ty (mod H),
+    // which repeats every lcm(W, H) = W * H / gcd(W, H)
+    (0..height / gcd(width, height))
+        .map(|k| tx + k * width)
+        .find(|t| t % height == ty)
+}
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn variance(positions: &[[i32; 2]], axis: usize) -> f64 {
+    let n = positions.len() as f64;
+    let mean = positions.iter().map(|p| p[axis] as f64).sum::<f64>() / n;
+    positions.iter().map(|p| (p[axis] as f64 - mean).powi(2)).sum::<f64>() / n
+}
+
+fn compute_safety_factor(counts: HashMap<(i32, i32), usize>, width: i32, height: i32) -> usize {
+    // for odd sides this skips the middle row/column, for even sides nothing is skipped
+    let (left, right) = (width / 2, (width + 1) / 2);
+    let (top, bottom) = (height / 2, (height + 1) / 2);
     let quadrant_counts = counts.iter()
     .fold([0, 0, 0, 0], |q, entry| {
             let (&(i, j), &count) = entry;
             [
-                q[0] + count * (i < height / 2 &&  j < width / 2) as usize,
-                q[1] + count * (i > height / 2 &&  j < width / 2) as usize,
-                q[2] + count * (i < height / 2 &&  j > width / 2) as usize,
-                q[3] + count * (i > height / 2 &&  j > width / 2) as usize
+                q[0] + count * (i < left &&  j < top) as usize,
+                q[1] + count * (i >= right &&  j < top) as usize,
+                q[2] + count * (i < left &&  j >= bottom) as usize,
+                q[3] + count * (i >= right &&  j >= bottom) as usize
             ]
         }
     );
     quadrant_counts.iter().fold(1_usize, |acc, &q| acc * q)
 }
 
-#[allow(dead_code)]
-fn print_robots(positions: &Vec<[i32; 2]>, height: i32, width: i32) {
+fn print_robots(positions: &Vec<[i32; 2]>, width: i32, height: i32) {
     let counts = count_robots(positions);
 
     println!();
-    for j in 0..width as i32 {
-        for i in 0..height as i32 {
+    for j in 0..height {
+        for i in 0..width {
             match counts.get(&(i, j)) {
                 None => print!("."),
                 Some(_) => print!("#")
@@ -0,0 +1,204 @@
+/*
+Interactive mode: drive the robot by hand on the live map.
+
+$ cargo run input.txt --interactive [--replay=moves.txt] [--record=moves.txt]
+
+Keys:
+w a s d / arrows   move the robot
+u                  undo the last move
+r                  redo
+n                  play the next move of the replay (the input's moves by default)
+m                  play all the remaining replay moves
+q / Ctrl-C         quit, printing the recorded moves
+
+The terminal is switched to unbuffered input with stty, so keys work without pressing enter,
+and switched back when the session ends, even on a panic. Ctrl-C is read as a key rather
+than a signal, so that it quits the same way as q. Keys are read on their own thread, so that a bare
+ESC can be told apart from the start of an arrow key by waiting briefly for the rest.
+*/
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+use utils::{get_arg, Direction};
+
+use crate::{parse_direction, Warehouse};
+
+// what a move changed, enough to take it back
+struct Move {
+    direction: Direction,
+    robot: (usize, usize),
+    pushed: Vec<usize>
+}
+
+struct Session {
+    warehouse: Warehouse,
+    // moves applied so far, as ^v<> symbols
+    recording: String,
+    undo: Vec<Move>,
+    // undone moves, replayed as they were since undo restores the warehouse exactly
+    redo: Vec<char>,
+    replay: Vec<char>,
+    replay_position: usize
+}
+
+impl Session {
+    fn apply(&mut self, symbol: char) {
+        self.redo.clear();
+        self.step(symbol);
+    }
+
+    fn step(&mut self, symbol: char) {
+        let direction = parse_direction(symbol).unwrap();
+        let robot = self.warehouse.robot;
+        let pushed = self.warehouse.step(direction);
+        self.undo.push(Move { direction, robot, pushed });
+        self.recording.push(symbol);
+    }
+
+    fn undo(&mut self) {
+        if let Some(last) = self.undo.pop() {
+            // the robot first, as the first pushed box moves back onto its cell
+            self.warehouse.move_robot(last.robot);
+            self.warehouse.move_boxes(&last.pushed, -last.direction);
+            self.redo.push(self.recording.pop().unwrap());
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(symbol) = self.redo.pop() {
+            self.step(symbol);
+        }
+    }
+
+    fn replay_next(&mut self) -> bool {
+        match self.replay.get(self.replay_position) {
+            Some(&symbol) => {
+                self.replay_position += 1;
+                self.apply(symbol);
+                true
+            },
+            None => false
+        }
+    }
+
+    fn draw(&self) {
+        let mut screen = String::from("\x1b[2J\x1b[H");
+        for row in &self.warehouse.map {
+            screen.extend(row);
+            screen.push('\n');
+        }
+        screen.push_str(&format!(
+            "\nGPS: {}   moves: {}   replay: {}/{}\n",
+            self.warehouse.gps(), self.recording.len(), self.replay_position, self.replay.len()
+        ));
+        screen.push_str("wasd/arrows: move  u: undo  r: redo  n: next replay move  m: play all  q: quit\n");
+        print!("{screen}");
+        io::stdout().flush().unwrap();
+    }
+}
+
+pub fn run(warehouse: Warehouse, input_directions: &str) -> io::Result<()> {
+    let replay = match get_arg::<String>("replay") {
+        Some(path) => fs::read_to_string(path)?,
+        None => input_directions.to_string()
+    };
+    let mut session = Session {
+        warehouse,
+        recording: String::new(),
+        undo: Vec::new(),
+        redo: Vec::new(),
+        replay: replay.chars().filter(|&c| parse_direction(c).is_some()).collect(),
+        replay_position: 0
+    };
+
+    let keys = spawn_key_reader();
+    let raw_mode = RawMode::enable();
+    loop {
+        session.draw();
+        match read_key(&keys) {
+            Some('w' | '^') => session.apply('^'),
+            Some('s' | 'v') => session.apply('v'),
+            Some('a' | '<') => session.apply('<'),
+            Some('d' | '>') => session.apply('>'),
+            Some('u') => session.undo(),
+            Some('r') => session.redo(),
+            Some('n') => { session.replay_next(); },
+            Some('m') => while session.replay_next() {},
+            Some('q' | '\x03') | None => break, // \x03 is Ctrl-C
+            Some(_) => {}
+        }
+    }
+    drop(raw_mode);
+
+    println!("{}", session.recording);
+    if let Some(path) = get_arg::<String>("record") {
+        fs::write(path, &session.recording)?;
+    }
+    Ok(())
+}
+
+/// Sends the bytes of stdin one by one, until it is closed.
+fn spawn_key_reader() -> Receiver<u8> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin();
+        let mut byte = [0];
+        while let Ok(1) = stdin.read(&mut byte) {
+            if sender.send(byte[0]).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Reads one key, translating arrow keys to the matching ^v<> symbol.
+/// Returns None when stdin is closed.
+fn read_key(keys: &Receiver<u8>) -> Option<char> {
+    let byte = keys.recv().ok()?;
+    if byte != 0x1b {
+        return Some(byte as char);
+    }
+    // escape sequence: ESC [ A-D, sent all at once, unlike a bare ESC
+    let mut sequence = [0; 2];
+    for part in &mut sequence {
+        match keys.recv_timeout(Duration::from_millis(50)) {
+            Ok(byte) => *part = byte,
+            Err(_) => return Some(' ')
+        }
+    }
+    Some(match sequence[1] {
+        b'A' => '^',
+        b'B' => 'v',
+        b'C' => '>',
+        b'D' => '<',
+        _ => ' '
+    })
+}
+
+/// Unbuffered terminal input for as long as it lives, so that the terminal is restored on
+/// every way out of the session, panics included.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> RawMode {
+        set_raw_mode(true);
+        RawMode
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        set_raw_mode(false);
+    }
+}
+
+fn set_raw_mode(enabled: bool) {
+    let args: &[&str] = if enabled { &["-icanon", "-echo", "-isig"] } else { &["icanon", "echo", "isig"] };
+    // not a terminal (e.g. keys piped in): nothing to do
+    let _ = Command::new("stty").args(args).stdin(Stdio::inherit()).stderr(Stdio::null()).status();
+}
@@ -66,178 +66,207 @@ fn move_robot(position, direction, boxes_to_move) {
             search_boxes(left edge + direction, direction, boxes_to_move)
             search_boxes(left edge + right + direction, direction, boxes_to_move)
 }
-*/
-use std::{collections::HashSet, io};
-use std::io::{stdin, stdout, Read, Write};
 
-use utils::{add_direction, read_array_from_string, read_from_args, Direction, E, N, S, W};
+Part 1 and 2 are the same simulation with boxes 1 or 2 cells wide, and it works the same
+for wider boxes, so the width is chosen at runtime:
+$ cargo run input.txt --scale=1
 
-const BOX_WIDTH: usize = 2;
+The warehouse keeps an index from each cell to the box covering it, so finding the box
+being pushed is a lookup, and each move only redraws the cells of the robot and the boxes
+it pushed. Long move lists on large warehouses take time proportional to the boxes moved.
 
-fn main() -> io::Result<()> {
-    let input = read_from_args()?;
+To explore edge cases by hand, there's an interactive mode (see interactive.rs):
+$ cargo run input.txt --interactive
+*/
+use std::{collections::HashSet, io};
 
-    let (map_raw, input_directions) = input.split_once("\n\n").unwrap();
-    let mut map = read_array_from_string(map_raw.to_string());
-    if BOX_WIDTH == 2 {
-        map = double_map(&map);
-    }
+use utils::{add_direction, get_arg, has_flag, read_array_from_string, read_from_args, Direction, E, N, S, W};
 
-    let (mut robot, mut boxes, walls) = parse_map(&map);
+mod interactive;
 
-    input_directions.chars().for_each(|direction_symbol| {
-        let direction: Direction = match direction_symbol {
-            '^' => N,
-            'v' => S,
-            '<' => W, 
-            '>' => E,
-            '\n' => return,
-            _ => panic!("Unexpected direction.")
-        };
+struct Warehouse {
+    map: Vec<Vec<char>>,
+    robot: (usize, usize),
+    // left edge of each box
+    boxes: Vec<(usize, usize)>,
+    // index into boxes of the box covering each cell
+    occupancy: Vec<Vec<Option<usize>>>,
+    box_width: usize
+}
 
-        let neighbor = add_direction(robot, direction);
-        let mut boxes_to_move = vec![];
+impl Warehouse {
+    fn new(map: Vec<Vec<char>>, box_width: usize) -> Self {
+        let map = scale_map(&map, box_width);
+        let (robot, boxes) = parse_map(&map);
+        let mut occupancy = vec![vec![None; map[0].len()]; map.len()];
+        for (id, b) in boxes.iter().enumerate() {
+            occupancy[b.0][b.1..b.1 + box_width].fill(Some(id));
+        }
+        Warehouse { map, robot, boxes, occupancy, box_width }
+    }
 
-        // print_map(&map);
-        // pause();
+    /// Moves the robot, redrawing only the cells of the robot and the boxes it pushed.
+    /// Returns the ids of the pushed boxes.
+    fn step(&mut self, direction: Direction) -> Vec<usize> {
+        let neighbor = add_direction(self.robot, direction);
+        let mut boxes_to_move = HashSet::new();
 
-        if search_boxes(neighbor, direction, &mut boxes_to_move, &boxes, &map) {
-            robot = add_direction(robot, direction);
-            boxes_to_move.iter().for_each( |&b| {
-                boxes[b] = add_direction(boxes[b], direction);
-            });
+        if !self.search_boxes(neighbor, direction, &mut boxes_to_move) {
+            return Vec::new();
         }
+        let pushed: Vec<usize> = boxes_to_move.into_iter().collect();
+        self.move_boxes(&pushed, direction);
+        self.move_robot(neighbor);
+        pushed
+    }
 
-        update_map(&mut map, &robot, &boxes, &walls);
-    });
-    print_map(&map);
-    let gps = boxes.iter().fold(0, 
-        |total, b| {
-            total + 100 * b.0 + b.1
+    fn move_boxes(&mut self, ids: &[usize], direction: Direction) {
+        // clear every box before drawing any, as their old and new cells overlap
+        for &id in ids {
+            let b = self.boxes[id];
+            self.map[b.0][b.1..b.1 + self.box_width].fill('.');
+            self.occupancy[b.0][b.1..b.1 + self.box_width].fill(None);
         }
-    );
-    dbg!(gps);
+        let symbols = box_symbols(self.box_width);
+        for &id in ids {
+            let b = add_direction(self.boxes[id], direction);
+            self.boxes[id] = b;
+            self.map[b.0][b.1..b.1 + self.box_width].copy_from_slice(&symbols);
+            self.occupancy[b.0][b.1..b.1 + self.box_width].fill(Some(id));
+        }
+    }
 
-    Ok(())
-}
+    fn move_robot(&mut self, position: (usize, usize)) {
+        self.map[self.robot.0][self.robot.1] = '.';
+        self.robot = position;
+        self.map[self.robot.0][self.robot.1] = '@';
+    }
 
-fn search_boxes(position: (usize, usize), direction: Direction, boxes_to_move: &mut Vec<usize>, boxes: &Vec<(usize, usize)>, map: &Vec<Vec<char>>, ) -> bool {    
-    let object = map[position.0][position.1];
-    match (direction, object) {
-        (_, '#') => {
-            *boxes_to_move = vec![]; // no boxes to move
-            false
-        },
-        (_, '.') => {
-            true
-        },
-        (_, 'O') => {
-            let box_index = boxes.iter().position(|&b| b == position).unwrap(); 
-            (*boxes_to_move).push(box_index);
-            let neighbor = add_direction(position, direction);
-            search_boxes(neighbor, direction, boxes_to_move, boxes, map)
-        },
-        (E | W, ']') => {
-            let neighbor = add_direction(position, direction);
-            search_boxes(neighbor, direction, boxes_to_move, boxes, map)
-        },
-        (N|S, ']') => {
-            let left_edge = add_direction(position, W);
-            search_boxes(left_edge, direction, boxes_to_move, boxes, map)
+    /// Collects the ids of the boxes pushed when moving into `position`.
+    /// Returns false, with no boxes to move, if a wall blocks the push.
+    fn search_boxes(&self, position: (usize, usize), direction: Direction, boxes_to_move: &mut HashSet<usize>) -> bool {
+        if self.map[position.0][position.1] == '#' {
+            boxes_to_move.clear(); // no boxes to move
+            return false;
+        }
+        let Some(id) = self.occupancy[position.0][position.1] else {
+            return true;
+        };
+        if !boxes_to_move.insert(id) {
+            return true;
         }
-        (_, '[') => {
-            let box_index = boxes.iter().position(|&b| b == position).unwrap();
-            if !boxes_to_move.contains(&box_index) {
-                (*boxes_to_move).push(box_index);
-            }
 
-            if direction == E || direction == W { // horizontal
-                let neighbor = add_direction(position, direction);
-                search_boxes(neighbor, direction, boxes_to_move, boxes, map)
+        let left_edge = self.boxes[id];
+        if direction == E || direction == W { // horizontal
+            let neighbor = if direction == E {
+                (left_edge.0, left_edge.1 + self.box_width)
             } else {
-                let neighbor_left = add_direction(position, direction);
-                let right_edge = add_direction(position, E);
-                let neighbor_right = add_direction(right_edge, direction);
-                search_boxes(neighbor_left, direction, boxes_to_move, boxes, map)
-                && search_boxes(neighbor_right, direction, boxes_to_move, boxes, map)
+                add_direction(left_edge, W)
+            };
+            self.search_boxes(neighbor, direction, boxes_to_move)
+        } else {
+            (0..self.box_width).all(|offset| {
+                let neighbor = add_direction((left_edge.0, left_edge.1 + offset), direction);
+                self.search_boxes(neighbor, direction, boxes_to_move)
+            })
+        }
+    }
+
+    fn gps(&self) -> usize {
+        self.boxes.iter().fold(0, 
+            |total, b| {
+                total + 100 * b.0 + b.1
             }
-        },
-        _ => panic!("Unexpected object in map.")
+        )
     }
 }
 
-fn update_map(map: &mut Vec<Vec<char>>, robot: &(usize, usize), boxes: &Vec<(usize, usize)>, walls: &HashSet<(usize, usize)>) {
-    for i in 0..map.len() {
-        for j in 0..map[0].len() {
-            map[i][j] = '.';
-        }
+fn main() -> io::Result<()> {
+    let input = read_from_args()?;
+
+    let (map_raw, input_directions) = input.split_once("\n\n").unwrap();
+    // 1 for part 1, 2 for part 2
+    let box_width = get_arg("scale").unwrap_or(2);
+    if box_width < 1 {
+        println!("--scale must be at least 1, boxes need a width.");
+        return Ok(());
     }
+    let mut warehouse = Warehouse::new(read_array_from_string(map_raw.to_string()), box_width);
 
-    map[robot.0][robot.1] = '@';
+    if has_flag("interactive") {
+        return interactive::run(warehouse, input_directions);
+    }
 
-    for b in boxes {
-        if BOX_WIDTH == 1 {
-            map[b.0][b.1] = 'O';
-        } else if BOX_WIDTH == 2 {
-            map[b.0][b.1] = '[';
-            map[b.0][b.1 + 1] = ']';
+    input_directions.chars().for_each(|direction_symbol| {
+        if direction_symbol == '\n' {
+            return;
         }
+        let direction = parse_direction(direction_symbol).expect("Unexpected direction.");
+        warehouse.step(direction);
+    });
+    print_map(&warehouse.map);
+    let gps = warehouse.gps();
+    dbg!(gps);
+
+    Ok(())
+}
+
+fn parse_direction(direction_symbol: char) -> Option<Direction> {
+    match direction_symbol {
+        '^' => Some(N),
+        'v' => Some(S),
+        '<' => Some(W), 
+        '>' => Some(E),
+        _ => None
     }
+}
 
-    for w in walls {
-        map[w.0][w.1] = '#';
+/// Characters used to draw a box: O for single cells, [] for two, [=...=] for wider ones.
+fn box_symbols(box_width: usize) -> Vec<char> {
+    if box_width == 1 {
+        return vec!['O'];
     }
+    let mut symbols = vec!['='; box_width];
+    symbols[0] = '[';
+    symbols[box_width - 1] = ']';
+    symbols
 }
 
-fn parse_map(map: &Vec<Vec<char>>) -> ((usize, usize), Vec<(usize, usize)>, HashSet<(usize, usize)>) {
+fn parse_map(map: &[Vec<char>]) -> ((usize, usize), Vec<(usize, usize)>) {
     let mut robot: (usize, usize) = (0, 0);
     let mut boxes: Vec<(usize, usize)> = Vec::new();
-    let mut walls: HashSet<(usize, usize)> = HashSet::new();
     
     for i in 0..map.len() {
         for j in 0..map[0].len() {
             match map[i][j] {
-                '.'|']' => continue,
-                '#' => {
-                    walls.insert((i, j));
-                },
-                '[' => boxes.push((i, j)),
-                'O' => boxes.push((i, j)),
+                '.' | '#' | '=' | ']' => continue,
+                '[' | 'O' => boxes.push((i, j)),
                 '@' => {robot = (i, j)},
                 _ => panic!("Unexpected map object.")
             }
         }
     }
-    (robot, boxes, walls)    
+    (robot, boxes)    
 }
 
-fn double_map(map: &Vec<Vec<char>>) -> Vec<Vec<char>> {
-    let mut doubled_map: Vec<Vec<char>> = Vec::new();
-    for i in 0..map.len() {
-        let mut doubled_line: Vec<char> = Vec::new();
-        for j in 0..map[0].len() {
-            match map[i][j] {
-                '.' => {
-                    doubled_line.extend(['.', '.']);
-                },
-                '#' => {
-                    doubled_line.extend(['#', '#']);
-                },
-                'O' => {
-                    doubled_line.extend(['[', ']']);
-                },
+/// Makes everything `box_width` times as wide, apart from the robot.
+fn scale_map(map: &[Vec<char>], box_width: usize) -> Vec<Vec<char>> {
+    map.iter().map(|line| {
+        line.iter().flat_map(|&object| {
+            match object {
+                '.' | '#' => vec![object; box_width],
+                'O' => box_symbols(box_width),
                 '@' => {
-                    doubled_line.extend(['@', '.']);
+                    let mut cells = vec!['.'; box_width];
+                    cells[0] = '@';
+                    cells
                 },
                 _ => panic!("Unexpected object in map")
-            };
-        }
-        doubled_map.push(doubled_line);
-    }
-    doubled_map
+            }
+        }).collect()
+    }).collect()
 }
 
-#[allow(dead_code)]
 fn print_map(map: &Vec<Vec<char>>) {
     for i in 0..map.len() {
         for j in 0..map[0].len() {
@@ -25,15 +25,34 @@ In order to know the full path, I have to also maintain the path so far in each
 , with termination conditions:
     - Reached E
     - Current score > minimum score (note that if ==, should add the path to seats and keep going)
+
+Carrying the path in every queue element gets expensive, and the same tile can be reached
+facing different ways, so this is now a Dijkstra over (tile, direction) states instead.
+Once every state has its lowest score, the tiles on the best paths are found by walking
+back from E along the moves whose scores add up exactly.
+
+The costs are parameters, and the best tiles or the scores can be drawn on the maze:
+$ cargo run input.txt --move-cost=1 --turn-cost=1000 --print --scores
 */
-use std::{collections::{HashMap, HashSet, VecDeque}, io, usize};
+use std::{cmp::Reverse, collections::{BinaryHeap, HashSet}, io};
 
 #[allow(unused_imports)]
-use utils::{Direction, DIRECTIONS4, E, add_direction, pause, read_array_from_string, read_from_args};
+use utils::{Direction, DIRECTIONS4, E, add_checked_direction, get_arg, has_flag, pause, read_array_from_string, read_from_args};
+
+struct Solution {
+    // None if E can't be reached
+    minimum_score: Option<usize>,
+    // tiles on at least one path with the minimum score
+    tiles: HashSet<(usize, usize)>,
+    // lowest score of each state, see state()
+    scores: Vec<usize>
+}
 
 fn main() -> io::Result<()> {
     let input = read_from_args()?;
     let maze = read_array_from_string(input);
+    let move_cost = get_arg("move-cost").unwrap_or(1);
+    let turn_cost = get_arg("turn-cost").unwrap_or(1000);
 
     let mut start: (usize, usize) = (0, 0);
     let mut end: (usize, usize) = (0, 0);
@@ -48,103 +67,127 @@ fn main() -> io::Result<()> {
         }
     }
 
-    let (minimum_score, num_seats) = find_minimum_score(&maze, start, end, E);
+    let solution = find_minimum_score(&maze, start, end, E, move_cost, turn_cost);
+    if has_flag("print") {
+        print_path(&maze, &solution.tiles);
+    }
+    if has_flag("scores") {
+        print_scores(&maze, &solution.scores);
+    }
+    let Some(minimum_score) = solution.minimum_score else {
+        println!("E can't be reached from S.");
+        return Ok(());
+    };
+    let num_seats = solution.tiles.len();
     dbg!(minimum_score, num_seats);
 
     Ok(())
 }
 
+/// A state is a tile and a facing direction, numbered as (i * width + j) * 4 + direction,
+/// where direction is the index in DIRECTIONS4.
+fn state(maze: &[Vec<char>], tile: (usize, usize), direction: usize) -> usize {
+    (tile.0 * maze[0].len() + tile.1) * 4 + direction
+}
+
+fn neighbor(maze: &[Vec<char>], tile: (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+    let next = add_checked_direction(tile, direction)?;
+    let open = maze.get(next.0).and_then(|row| row.get(next.1)).is_some_and(|&t| t != '#');
+    open.then_some(next)
+}
+
+/// Dijkstra over (tile, direction) states: a move forward costs `move_cost` and a turn of
+/// 90 degrees on the spot costs `turn_cost`. The tiles on the best paths are then found by
+/// walking back from E along the moves that are tight, i.e. score[from] + cost == score[to].
 fn find_minimum_score(
-    maze: &Vec<Vec<char>>,
+    maze: &[Vec<char>],
     start: (usize, usize),
     end: (usize, usize),
-    starting_direction: Direction
-) -> (usize, usize) {
-    let mut queue:
-        VecDeque<((usize,usize), Direction, usize, HashSet<(usize, usize)>)>
-         = VecDeque::new(); // ((i, j), direction, score, path)
-
-    let mut path: HashSet<(usize, usize)> = HashSet::new();
-    let mut seats: HashSet<(usize, usize)> = HashSet::new();
-    let mut visited_tiles: HashMap<((usize, usize), Direction), usize> = HashMap::new();
-
-    path.insert(start);
-    queue.push_back((start, starting_direction, 0, path));
-
-    let mut minimum_score = usize::MAX;
-
-    while !queue.is_empty() {
-        let (tile, current_direction, score, path) = queue.pop_front().unwrap();
-
-        // if I've been here before, terminate the longest path
-        match visited_tiles.get_mut(&(tile, current_direction)) {
-            None => {
-                visited_tiles.insert((tile, current_direction), score); 
-            },
-            Some(existing_score) => {
-                if score <= *existing_score {
-                    *existing_score = score;
-                } else {
-                    continue;
-                }
-            }
+    starting_direction: Direction,
+    move_cost: usize,
+    turn_cost: usize
+) -> Solution {
+    let mut scores = vec![usize::MAX; maze.len() * maze[0].len() * 4];
+    let mut queue = BinaryHeap::new(); // lowest (score, tile, direction) first
+
+    let start_direction = DIRECTIONS4.iter().position(|&d| d == starting_direction).unwrap();
+    scores[state(maze, start, start_direction)] = 0;
+    queue.push(Reverse((0, start, start_direction)));
+
+    while let Some(Reverse((score, tile, direction))) = queue.pop() {
+        if score > scores[state(maze, tile, direction)] {
+            continue;
         }
 
-        // print_path(&maze, &path);
-        // pause();
-
-        if tile == end {
-            if score < minimum_score {
-                minimum_score = score;
-                seats = path;
-            } else if score == minimum_score {
-                seats.extend(path);
+        let mut next_states = vec![
+            ((direction + 1) % 4, tile, score + turn_cost),
+            ((direction + 3) % 4, tile, score + turn_cost)
+        ];
+        if let Some(next) = neighbor(maze, tile, DIRECTIONS4[direction]) {
+            next_states.push((direction, next, score + move_cost));
+        }
+        for (new_direction, new_tile, new_score) in next_states {
+            let new_state = state(maze, new_tile, new_direction);
+            if new_score < scores[new_state] {
+                scores[new_state] = new_score;
+                queue.push(Reverse((new_score, new_tile, new_direction)));
             }
-            continue;
         }
-        
-        for direction in DIRECTIONS4 {
-            let new_tile = add_direction(tile, direction);
-            // skip walls
-            if maze[new_tile.0][new_tile.1] == '#' {
-                continue;
+    }
+
+    let minimum_score = (0..4).map(|d| scores[state(maze, end, d)]).min().filter(|&score| score < usize::MAX);
+    let mut tiles: HashSet<(usize, usize)> = HashSet::new();
+    if let Some(minimum_score) = minimum_score {
+        let mut stack: Vec<((usize, usize), usize)> = (0..4)
+            .filter(|&d| scores[state(maze, end, d)] == minimum_score)
+            .map(|d| (end, d))
+            .collect();
+        let mut visited: HashSet<((usize, usize), usize)> = stack.iter().copied().collect();
+
+        while let Some((tile, direction)) = stack.pop() {
+            tiles.insert(tile);
+            let score = scores[state(maze, tile, direction)];
+
+            let mut previous_states = vec![
+                ((direction + 1) % 4, tile, turn_cost),
+                ((direction + 3) % 4, tile, turn_cost)
+            ];
+            if let Some(previous) = neighbor(maze, tile, -DIRECTIONS4[direction]) {
+                previous_states.push((direction, previous, move_cost));
             }
-            // never go back
-            if direction == -current_direction {
-                continue;
+            for (previous_direction, previous_tile, cost) in previous_states {
+                let previous_score = scores[state(maze, previous_tile, previous_direction)];
+                if previous_score != usize::MAX
+                && previous_score + cost == score
+                && visited.insert((previous_tile, previous_direction)) {
+                    stack.push((previous_tile, previous_direction));
+                }
             }
-
-            let new_score = score + if direction == current_direction {
-                1
-            } else {
-                1001
-            };
-
-            let mut updated_path = path.clone();
-            updated_path.insert(new_tile);
-            queue.push_back((new_tile, direction, new_score, updated_path));
         }
     }
-    (minimum_score, seats.len())
+    Solution { minimum_score, tiles, scores }
 }
 
-#[allow(dead_code)]
-fn print_scores(maze: &Vec<Vec<char>>, visited_tiles: &HashMap<(usize, usize), usize>) {
+/// Lowest score of each tile, over the directions it can be faced in.
+fn print_scores(maze: &[Vec<char>], scores: &[usize]) {
     for (i, row) in maze.iter().enumerate() {
         for (j, tile) in row.iter().enumerate() {
             if *tile == '#' {
                 print!("{tile:6}");
             } else {
-                let score = visited_tiles.get(&(i, j)).unwrap_or(&0);
-                print!("{score:6}")
+                let score = (0..4).map(|d| scores[state(maze, (i, j), d)]).min().unwrap();
+                if score == usize::MAX {
+                    print!("{:6}", "");
+                } else {
+                    print!("{score:6}");
+                }
             }
         }
         println!();
     }
 }
 
-#[allow(dead_code)]
-fn print_path(maze: &Vec<Vec<char>>, path: &HashSet<(usize, usize)>) {
+fn print_path(maze: &[Vec<char>], path: &HashSet<(usize, usize)>) {
     for (i, row) in maze.iter().enumerate() {
         for (j, tile) in row.iter().enumerate() {
             if matches!(*tile, '#'|'E'|'S') {
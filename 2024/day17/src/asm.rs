@@ -0,0 +1,116 @@
+/*
+Disassembler and assembler for the 3-bit computer.
+
+The listing has one instruction per line, prefixed by its address:
+
+ 0: bst A         ; B = A % 8
+ 2: bxl 1         ; B = B ^ 1
+ ...
+
+Combo operands are written as 0-3 or A, B, C (7 is reserved and kept as is so that
+any program round trips). `bxc` ignores its operand but it's still printed, since it
+is part of the program output for quines.
+
+The assembler accepts the same syntax, with or without the addresses. Comments start
+with ';' or '#', and `name:` defines a label that can be used as a jump target.
+*/
+use std::collections::HashMap;
+
+pub const MNEMONICS: [&str; 8] = ["adv", "bxl", "bst", "jnz", "bxc", "out", "bdv", "cdv"];
+
+/// Fails on programs that have no listing: values above 7, or a last opcode without an
+/// operand (which the assembler couldn't give back without adding one).
+pub fn disassemble(program: &[usize]) -> Result<String, String> {
+    if let Some(&value) = program.iter().find(|&&value| value > 7) {
+        return Err(format!("Program contains {value}, which is not a 3-bit number."));
+    }
+    if !program.len().is_multiple_of(2) {
+        return Err(format!("Program ends with opcode {} at {} without an operand.", program[program.len() - 1], program.len() - 1));
+    }
+    Ok(program.chunks(2).enumerate().map(|(i, instruction)| {
+        let (opcode, operand) = (instruction[0], instruction[1]);
+        format!("{:2}: {:<13} ; {}", 2 * i, format_instruction(opcode, operand), describe(opcode, operand))
+    }).collect::<Vec<String>>().join("\n"))
+}
+
+pub fn format_instruction(opcode: usize, operand: usize) -> String {
+    let operand = if matches!(opcode, 0 | 2 | 5 | 6 | 7) {
+        combo_name(operand)
+    } else {
+        operand.to_string()
+    };
+    format!("{} {}", MNEMONICS[opcode], operand)
+}
+
+fn combo_name(operand: usize) -> String {
+    match operand {
+        4 => "A".to_string(),
+        5 => "B".to_string(),
+        6 => "C".to_string(),
+        _ => operand.to_string()
+    }
+}
+
+fn describe(opcode: usize, operand: usize) -> String {
+    let combo = combo_name(operand);
+    match opcode {
+        0 => format!("A = A >> {combo}"),
+        1 => format!("B = B ^ {operand}"),
+        2 => format!("B = {combo} % 8"),
+        3 => format!("jump to {operand} if A != 0"),
+        4 => "B = B ^ C".to_string(),
+        5 => format!("print {combo} % 8"),
+        6 => format!("B = A >> {combo}"),
+        7 => format!("C = A >> {combo}"),
+        _ => unreachable!()
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Vec<usize>, String> {
+    // first pass: strip comments and addresses, and collect labels
+    let mut lines: Vec<(usize, &str)> = Vec::new();
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    for (line_number, line) in source.lines().enumerate() {
+        let line = line.split([';', '#']).next().unwrap().trim();
+        let line = match line.split_once(':') {
+            Some((prefix, rest)) if prefix.trim().parse::<usize>().is_ok() => rest.trim(),
+            Some((label, rest)) => {
+                labels.insert(label.trim(), 2 * lines.len());
+                rest.trim()
+            },
+            None => line
+        };
+        if !line.is_empty() {
+            lines.push((line_number + 1, line));
+        }
+    }
+
+    let mut program = Vec::new();
+    for (line_number, line) in lines {
+        let mut parts = line.split_whitespace();
+        let mnemonic = parts.next().unwrap().to_lowercase();
+        let opcode = MNEMONICS.iter().position(|&m| m == mnemonic)
+            .ok_or(format!("line {line_number}: unknown instruction '{mnemonic}'"))?;
+
+        let operand = match (parts.next(), opcode) {
+            (None, 4) => 0,
+            (None, _) => return Err(format!("line {line_number}: '{mnemonic}' needs an operand")),
+            (Some("A" | "a"), 0 | 2 | 5 | 6 | 7) => 4,
+            (Some("B" | "b"), 0 | 2 | 5 | 6 | 7) => 5,
+            (Some("C" | "c"), 0 | 2 | 5 | 6 | 7) => 6,
+            (Some(label), 3) if labels.contains_key(label) => match labels[label] {
+                address if address <= 7 => address,
+                address => return Err(format!("line {line_number}: label '{label}' at {address} is out of jump range"))
+            },
+            (Some(operand), _) => match operand.parse::<usize>() {
+                Ok(value) if value <= 7 => value,
+                _ => return Err(format!("line {line_number}: invalid operand '{operand}' for '{mnemonic}'"))
+            }
+        };
+        if let Some(extra) = parts.next() {
+            return Err(format!("line {line_number}: unexpected '{extra}'"));
+        }
+        program.extend([opcode, operand]);
+    }
+    Ok(program)
+}
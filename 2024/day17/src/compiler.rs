@@ -0,0 +1,125 @@
+/*
+Compiles a program once into a list of specialised closures, so candidate values of A can be
+checked without decoding opcodes and combo operands on every step.
+
+Each closure already knows its operand: literal combos are folded into constants, register
+combos read the register directly, and the 2^COMBO(X) divisions are right shifts.
+Outputs are passed to a callback instead of being collected, which lets `matches` stop at
+the first wrong value.
+*/
+use crate::MAX_STEPS;
+
+enum Flow {
+    Next,
+    Jump(usize),
+    Output(usize)
+}
+
+type Instruction = Box<dyn Fn(&mut [usize; 3]) -> Flow>;
+
+pub struct CompiledProgram {
+    instructions: Vec<Instruction>
+}
+
+impl CompiledProgram {
+    pub fn compile(program: &[usize]) -> Self {
+        // one entry per instruction pointer, so jumps to odd addresses still work
+        let instructions = program.windows(2)
+            .map(|instruction| compile_instruction(instruction[0], instruction[1]))
+            .collect();
+        CompiledProgram { instructions }
+    }
+
+    /// Runs the program, calling `on_output` for every value until it returns false.
+    /// Returns true if the program halted on its own.
+    pub fn run(&self, a: usize, on_output: impl FnMut(usize) -> bool) -> bool {
+        self.run_within(a, MAX_STEPS, on_output).0
+    }
+
+    /// Like `run`, giving up after `max_steps` instructions.
+    /// Also returns how many instructions were executed.
+    pub fn run_within(&self, a: usize, max_steps: usize, mut on_output: impl FnMut(usize) -> bool) -> (bool, usize) {
+        let mut registers = [a, 0, 0];
+        let mut instruction_pointer = 0;
+
+        for steps in 0..max_steps {
+            let Some(instruction) = self.instructions.get(instruction_pointer) else {
+                return (true, steps);
+            };
+            instruction_pointer = match instruction(&mut registers) {
+                Flow::Next => instruction_pointer + 2,
+                Flow::Jump(target) => target,
+                Flow::Output(value) => {
+                    if !on_output(value) {
+                        return (false, steps + 1);
+                    }
+                    instruction_pointer + 2
+                }
+            };
+        }
+        (false, max_steps)
+    }
+
+    pub fn output(&self, a: usize, max_len: usize) -> Vec<usize> {
+        let mut output_buffer = Vec::new();
+        self.run(a, |value| {
+            output_buffer.push(value);
+            output_buffer.len() <= max_len
+        });
+        output_buffer
+    }
+
+    /// Checks the output against `expected` as it's produced, stopping at the first mismatch.
+    pub fn matches(&self, a: usize, expected: &[usize]) -> bool {
+        self.matches_within(a, expected, MAX_STEPS).0
+    }
+
+    /// Like `matches`, giving up after `max_steps` instructions.
+    /// Also returns how many instructions were executed.
+    pub fn matches_within(&self, a: usize, expected: &[usize], max_steps: usize) -> (bool, usize) {
+        let mut matched = 0;
+        let (halted, steps) = self.run_within(a, max_steps, |value| {
+            if expected.get(matched) != Some(&value) {
+                return false;
+            }
+            matched += 1;
+            true
+        });
+        (halted && matched == expected.len(), steps)
+    }
+}
+
+fn compile_instruction(opcode: usize, operand: usize) -> Instruction {
+    match (opcode, operand) {
+        // A = A / 2^X
+        (0, 0..=3) => Box::new(move |r| { r[0] >>= operand; Flow::Next }),
+        (0, 4..=6) => Box::new(move |r| { r[0] = shr(r[0], r[operand - 4]); Flow::Next }),
+        // B = B ^ X
+        (1, _) => Box::new(move |r| { r[1] ^= operand; Flow::Next }),
+        // B = COMBO(X) % 8
+        (2, 0..=3) => Box::new(move |r| { r[1] = operand; Flow::Next }),
+        (2, 4..=6) => Box::new(move |r| { r[1] = r[operand - 4] & 7; Flow::Next }),
+        // jump X if A != 0
+        (3, _) => Box::new(move |r| if r[0] != 0 { Flow::Jump(operand) } else { Flow::Next }),
+        // B = B ^ C
+        (4, _) => Box::new(|r| { r[1] ^= r[2]; Flow::Next }),
+        // print COMBO(X) % 8
+        (5, 0..=3) => Box::new(move |_| Flow::Output(operand)),
+        (5, 4..=6) => Box::new(move |r| Flow::Output(r[operand - 4] & 7)),
+        // B = A / 2^X, C = A / 2^X
+        (6 | 7, 0..=3) => Box::new(move |r| { r[opcode - 5] = r[0] >> operand; Flow::Next }),
+        (6 | 7, 4..=6) => Box::new(move |r| { r[opcode - 5] = shr(r[0], r[operand - 4]); Flow::Next }),
+        // only fails if the program actually gets here
+        _ => Box::new(move |_| panic!("invalid instruction {opcode},{operand}"))
+    }
+}
+
+fn shr(value: usize, shift: usize) -> usize {
+    u32::try_from(shift).ok().and_then(|shift| value.checked_shr(shift)).unwrap_or(0)
+}
+
+/// Tries every A in 1..limit, for programs small enough to be brute forced.
+pub fn brute_force(program: &[usize], limit: usize) -> Option<usize> {
+    let compiled = CompiledProgram::compile(program);
+    (1..limit).find(|&a| compiled.matches(a, program))
+}
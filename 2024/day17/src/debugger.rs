@@ -0,0 +1,137 @@
+/*
+Step debugger for the 3-bit computer.
+
+Runs the program one instruction at a time, optionally logging every step and
+stopping on breakpoints. When stopped, commands are read from stdin:
+
+s, step or empty line   execute one instruction
+c, continue             run until the next breakpoint
+b N                     add a breakpoint at instruction pointer N
+d N                     delete the breakpoint at N
+o                       toggle stopping after each output
+t                       toggle tracing
+a/b/c = N               set a register, e.g. "a = 729"
+r                       print the registers
+q, quit                 abort the program
+*/
+use std::io::{self, BufRead, Write};
+
+use utils::{get_arg, has_flag};
+
+use crate::{asm, halted, step, Registers, MAX_STEPS};
+
+pub struct Debugger {
+    pub trace: bool,
+    pub breakpoints: Vec<usize>,
+    pub break_on_output: bool,
+    pub max_steps: usize,
+    // stop before the next instruction
+    stepping: bool
+}
+
+impl Debugger {
+    pub fn from_args() -> Self {
+        let breakpoints = get_arg::<String>("break").map(|list| {
+            list.split(',').map(|ip| ip.parse().expect("breakpoints should be instruction pointers")).collect()
+        }).unwrap_or_default();
+
+        Debugger {
+            trace: has_flag("trace"),
+            breakpoints,
+            break_on_output: has_flag("break-on-output"),
+            max_steps: get_arg("max-steps").unwrap_or(MAX_STEPS),
+            stepping: has_flag("step")
+        }
+    }
+
+    pub fn run(&mut self, program: &[usize], registers: &mut Registers) -> Result<Vec<usize>, String> {
+        let mut instruction_pointer = 0;
+        let mut output_buffer: Vec<usize> = Vec::new();
+
+        for steps in 0..self.max_steps {
+            if halted(program, instruction_pointer) {
+                return Ok(output_buffer);
+            }
+
+            if self.stepping || self.breakpoints.contains(&instruction_pointer) {
+                print_state(steps, instruction_pointer, program, registers);
+                if !self.prompt(registers) {
+                    return Err(format!("Program aborted at instruction {instruction_pointer}."));
+                }
+            }
+
+            let outputs = output_buffer.len();
+            let new_instruction_pointer = step(program, registers, instruction_pointer, &mut output_buffer);
+            if self.trace {
+                print_state(steps, instruction_pointer, program, registers);
+            }
+            if output_buffer.len() > outputs {
+                if self.trace {
+                    println!("  output: {}", output_buffer[outputs]);
+                }
+                if self.break_on_output {
+                    self.stepping = true;
+                }
+            }
+            instruction_pointer = new_instruction_pointer;
+        }
+        Err(format!("Program didn't halt after {} steps, A = {}.", self.max_steps, registers.a))
+    }
+
+    /// Reads commands until the program should resume. Returns false to abort.
+    fn prompt(&mut self, registers: &mut Registers) -> bool {
+        let stdin = io::stdin();
+        loop {
+            print!("(debug) ");
+            io::stdout().flush().unwrap();
+
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap() == 0 {
+                return false;
+            }
+            let command: Vec<&str> = line.split_whitespace().collect();
+            match command.as_slice() {
+                [] | ["s" | "step"] => {
+                    self.stepping = true;
+                    return true;
+                },
+                ["c" | "continue"] => {
+                    self.stepping = false;
+                    return true;
+                },
+                ["q" | "quit"] => return false,
+                ["b", ip] | ["d", ip] => match ip.parse::<usize>() {
+                    Ok(ip) if command[0] == "b" => self.breakpoints.push(ip),
+                    Ok(ip) => self.breakpoints.retain(|&breakpoint| breakpoint != ip),
+                    Err(_) => println!("invalid instruction pointer: {ip}")
+                },
+                ["o"] => {
+                    self.break_on_output = !self.break_on_output;
+                    println!("break on output: {}", self.break_on_output);
+                },
+                ["t"] => {
+                    self.trace = !self.trace;
+                    println!("trace: {}", self.trace);
+                },
+                ["r"] => println!("{registers:?}"),
+                [register @ ("a" | "b" | "c"), "=", value] => match value.parse::<usize>() {
+                    Ok(value) => match *register {
+                        "a" => registers.a = value,
+                        "b" => registers.b = value,
+                        _ => registers.c = value
+                    },
+                    Err(_) => println!("invalid register value: {value}")
+                },
+                _ => println!("unknown command: {}", line.trim())
+            }
+        }
+    }
+}
+
+fn print_state(steps: usize, instruction_pointer: usize, program: &[usize], registers: &Registers) {
+    let (opcode, operand) = (program[instruction_pointer], program[instruction_pointer + 1]);
+    println!(
+        "[{steps:>6}] {instruction_pointer:2}: {:<8} A={} B={} C={}",
+        asm::format_instruction(opcode, operand), registers.a, registers.b, registers.c
+    );
+}
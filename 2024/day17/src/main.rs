@@ -25,14 +25,25 @@ COMBO(X) = {
     C if X=6
 }
 
+To see a program as a listing, or to write one by hand:
+$ cargo run input.txt --disassemble > program.asm
+$ cargo run program.asm --assemble
+
+To debug a program step by step:
+$ cargo run input.txt --trace            (log every instruction)
+$ cargo run input.txt --step             (interactive single stepping)
+$ cargo run input.txt --break=6,10       (stop at these instruction pointers)
+$ cargo run input.txt --break-on-output  (stop after every out)
+$ cargo run input.txt --max-steps=1000   (give up on programs that never halt)
+
+Small programs can also be brute forced for part 2, using a compiled version of the program:
+$ cargo run example2.txt --brute-force --limit=1000000
+
 Part 2: which initial value for register A causes the program to output a copy of itself?
 
-Approach: let's assume the program is always a while loop on A>0 (always ends with 3, 0).
-For any program like this to halt, A has to be modified and the only instruction that does this
-is adv. Let's further assume that adv is always called with operand 3 (that's the case for
-the second example and for my input).
+Approach: the easy case is a while loop on A>0 (ends with 3, 0) that right shifts A by a
+constant number of bits per iteration and prints one value, e.g.
 
-E.g.
 0,3,5,4,3,0
 
 the instructions are:
@@ -40,16 +51,21 @@ while A > 0 :
     A = A / 8
     print A % 8
 
-So at each step, the program right shifts A by 3 bits and prints the last 3 bits.
-
-Do a BFS where each edge left shifts by 3 and adds a number between 0 and 7.
-Can terminate paths where the output diverges from the program.
+Then A can be rebuilt 3 bits at a time with a BFS, dropping paths where the output diverges
+from the program. Programs that don't fit this shape fall back to a bit by bit constraint
+search, see solver.rs.
 
 */
-use std::{collections::VecDeque, io};
+use std::io;
+
+use utils::{get_arg, has_flag, read_from_args};
 
-#[allow(unused_imports)]
-use utils::{pause, read_from_args};
+mod asm;
+mod compiler;
+mod debugger;
+mod solver;
+
+const MAX_STEPS: usize = 1_000_000;
 
 #[derive(Debug, Clone, Copy)]
 struct Registers {
@@ -60,6 +76,14 @@ struct Registers {
 
 fn main() -> io::Result<()>{
     let input = read_from_args()?;
+    if has_flag("assemble") {
+        match asm::assemble(&input) {
+            Ok(program) => println!("Program: {}", program.iter().map(|num| num.to_string()).collect::<Vec<String>>().join(",")),
+            Err(message) => println!("{message}")
+        }
+        return Ok(());
+    }
+
     let mut lines = input.lines();
 
     fn parse_register(line: &str) -> usize {
@@ -77,70 +101,57 @@ fn main() -> io::Result<()>{
     let program_string = &lines.next().unwrap()[9..]; 
     let program = program_string.split(",").map(|num| num.parse::<usize>().unwrap()).collect::<Vec<usize>>();
     // dbg!(&program);
+    if has_flag("disassemble") {
+        match asm::disassemble(&program) {
+            Ok(listing) => println!("{listing}"),
+            Err(message) => println!("{message}")
+        }
+        return Ok(());
+    }
+
+    let mut debugger = debugger::Debugger::from_args();
+    match debugger.run(&program, &mut registers) {
+        Ok(output_buffer) => {
+            let output = output_buffer.iter().map(|num| num.to_string()).collect::<Vec<String>>().join(",");
+            dbg!(output);
+        },
+        Err(message) => println!("{message}")
+    }
 
-    let output_buffer = execute(&program, &mut registers);
-    let output = output_buffer.iter().map(|num| num.to_string()).collect::<Vec<String>>().join(",");
-    dbg!(output);
-
-    let mut queue: VecDeque<usize> = VecDeque::new();
-    queue.push_back(0);
-
-    'bfs: while !queue.is_empty() {
-        // println!("{:?}", &queue);
-        let a = queue.pop_front().unwrap();
-
-        // let output_buffer = execute_with(&program, a);
-        // println!("{:?}", &output_buffer);
-        // pause();
-        for num in 0..8 {
-            let new_a = (a << 3) + num;
-            let output_buffer = execute_with(&program, new_a);
-            // println!("{num} => {:?}", &output_buffer);
-            if output_buffer != program[program.len() - output_buffer.len()..] {
-                continue;
-            }
-            if output_buffer == program {
-                dbg!(new_a);
-                break 'bfs;
-            }
-            queue.push_back(new_a);
+    if has_flag("brute-force") {
+        let limit = get_arg("limit").unwrap_or(1 << 32);
+        match compiler::brute_force(&program, limit) {
+            Some(a) => { dbg!(a); },
+            None => println!("No value of A below {limit} makes the program output itself.")
         }
+        return Ok(());
+    }
+
+    match solver::find_quine(&program) {
+        Ok(a) => { dbg!(a); },
+        Err(message) => println!("{message}")
     }
     Ok(())
 }
 
-fn execute_with(program: &Vec<usize>, a: usize) -> Vec<usize> {
-    let mut registers = Registers {
-        a: a,
-        b: 0,
-        c: 0
-    };
-    execute(program, &mut registers)
+fn halted(program: &[usize], instruction_pointer: usize) -> bool {
+    // an opcode without an operand also halts the program
+    instruction_pointer + 1 >= program.len()
 }
 
-fn execute(program: &Vec<usize>, registers: &mut Registers) -> Vec<usize> {
-    let mut instruction_pointer = 0;
-    let mut output_buffer: Vec<usize> = Vec::new();
-
-    loop {
-        if instruction_pointer >= program.len() {
-            break;
-        }
-
-        let (opcode, operand) = (program[instruction_pointer], program[instruction_pointer + 1]);
-        instruction_pointer = match opcode {
-            0 => adv(operand, registers, instruction_pointer, &mut output_buffer),
-            1 => bxl(operand, registers, instruction_pointer, &mut output_buffer),
-            2 => bst(operand, registers, instruction_pointer, &mut output_buffer),
-            3 => jnz(operand, registers, instruction_pointer, &mut output_buffer),
-            4 => bxc(operand, registers, instruction_pointer, &mut output_buffer),
-            5 => out(operand, registers, instruction_pointer, &mut output_buffer),
-            6 => bdv(operand, registers, instruction_pointer, &mut output_buffer),
-            7 => cdv(operand, registers, instruction_pointer, &mut output_buffer),
-            _ => unreachable!(),
-        };
+fn step(program: &[usize], registers: &mut Registers, instruction_pointer: usize, output_buffer: &mut Vec<usize>) -> usize {
+    let (opcode, operand) = (program[instruction_pointer], program[instruction_pointer + 1]);
+    match opcode {
+        0 => adv(operand, registers, instruction_pointer, output_buffer),
+        1 => bxl(operand, registers, instruction_pointer, output_buffer),
+        2 => bst(operand, registers, instruction_pointer, output_buffer),
+        3 => jnz(operand, registers, instruction_pointer, output_buffer),
+        4 => bxc(operand, registers, instruction_pointer, output_buffer),
+        5 => out(operand, registers, instruction_pointer, output_buffer),
+        6 => bdv(operand, registers, instruction_pointer, output_buffer),
+        7 => cdv(operand, registers, instruction_pointer, output_buffer),
+        _ => unreachable!(),
     }
-    output_buffer
 }
 
 fn adv(operand: usize, registers: &mut Registers, instruction_pointer: usize, _output_buffer: &mut Vec<usize>) -> usize {
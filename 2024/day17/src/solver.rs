@@ -0,0 +1,305 @@
+/*
+Part 2 solver: find the smallest initial A for which the program outputs itself.
+
+Most programs are a single loop `while A != 0 { ...; out; ...; A = A >> k }`, where
+each iteration consumes k bits of A and B, C are recomputed from A every time.
+For those, the output of the last iteration only depends on the highest bits of A,
+so A can be built k bits at a time from the top (see `digit_search`).
+
+Anything else goes through `constraint_search`, which fixes the bits of A one at a time
+from the least significant end and runs the program on a partially known A. Each register
+is tracked as a bit-vector with a mask of known bits, and a branch is pruned as soon as
+an output is fully known and doesn't match the program. Shifting by an unknown amount loses
+every bit, so nothing may ever get pruned, and a program may never halt: the search gives up
+once it has executed SEARCH_BUDGET instructions in all.
+*/
+use std::collections::VecDeque;
+
+use crate::{compiler::CompiledProgram, halted};
+
+// partial execution gives up on a branch after this many instructions
+const MAX_STEPS: usize = 100_000;
+const SEARCH_BUDGET: usize = 1 << 30;
+
+pub fn find_quine(program: &[usize]) -> Result<usize, String> {
+    validate(program)?;
+    let compiled = CompiledProgram::compile(program);
+
+    let quine = match analyse(program) {
+        Ok(shift) => {
+            println!("Program is a single loop consuming {shift} bits of A per iteration.");
+            match digit_search(program, &compiled, shift) {
+                Some(a) => Some(a),
+                None => constraint_search(program, &compiled)?
+            }
+        },
+        Err(reason) => {
+            println!("Falling back to constraint search: {reason}");
+            constraint_search(program, &compiled)?
+        }
+    };
+    quine.ok_or("No value of A below 2^64 makes the program output itself.".to_string())
+}
+
+/// Rejects programs that can't be run at all, or that can never halt for A != 0.
+fn validate(program: &[usize]) -> Result<(), String> {
+    if !program.len().is_multiple_of(2) {
+        return Err(format!("Program has an odd number of values ({}).", program.len()));
+    }
+    if let Some(&value) = program.iter().find(|&&value| value > 7) {
+        return Err(format!("Program contains {value}, which is not a 3-bit number."));
+    }
+    for (ip, instruction) in program.chunks(2).enumerate() {
+        let (opcode, operand) = (instruction[0], instruction[1]);
+        if uses_combo(opcode) && operand == 7 {
+            return Err(format!("Instruction {} uses the reserved combo operand 7.", 2 * ip));
+        }
+    }
+    if !program.chunks(2).any(|instruction| instruction[0] == 5) {
+        return Err("Program never outputs anything.".to_string());
+    }
+    if !program.chunks(2).any(|instruction| instruction[0] == 0) {
+        return Err("Program never modifies A, so it loops forever or runs once.".to_string());
+    }
+    Ok(())
+}
+
+/// Checks that the program is a single loop with one output and a constant shift of A,
+/// and returns how many bits of A each iteration consumes.
+fn analyse(program: &[usize]) -> Result<u32, String> {
+    let instructions: Vec<(usize, usize)> = program.chunks(2).map(|i| (i[0], i[1])).collect();
+    let (body, last) = instructions.split_at(instructions.len() - 1);
+
+    if last[0] != (3, 0) {
+        return Err("Program doesn't end with a jump back to the start (3,0).".to_string());
+    }
+    if body.iter().any(|&(opcode, _)| opcode == 3) {
+        return Err("Program has more than one jump.".to_string());
+    }
+    if body.iter().filter(|&&(opcode, _)| opcode == 5).count() != 1 {
+        return Err("Loop body doesn't output exactly one value.".to_string());
+    }
+
+    let mut shift = 0;
+    for &(opcode, operand) in body {
+        if opcode == 0 {
+            if operand > 3 {
+                return Err(format!("A is shifted by a register value (adv {operand})."));
+            }
+            shift += operand as u32;
+        }
+    }
+    if shift == 0 {
+        return Err("Loop doesn't shift A.".to_string());
+    }
+
+    let mut carried = Vec::new();
+    let mut written = [false; 3];
+    for &(opcode, operand) in body {
+        let mut reads = Vec::new();
+        if uses_combo(opcode) && (4..=6).contains(&operand) {
+            reads.push(operand - 4);
+        }
+        match opcode {
+            1 => reads.push(1),
+            4 => reads.extend([1, 2]),
+            _ => {}
+        }
+        for register in reads {
+            if register != 0 && !written[register] {
+                let name = ['A', 'B', 'C'][register];
+                if !carried.contains(&name) {
+                    carried.push(name);
+                }
+            }
+        }
+        match opcode {
+            0 => written[0] = true,
+            1 | 2 | 4 | 6 => written[1] = true,
+            7 => written[2] = true,
+            _ => {}
+        }
+    }
+    if !carried.is_empty() {
+        return Err(format!("Registers {carried:?} carry over between iterations."));
+    }
+
+    Ok(shift)
+}
+
+fn uses_combo(opcode: usize) -> bool {
+    matches!(opcode, 0 | 2 | 5 | 6 | 7)
+}
+
+/// Do a BFS where each edge left shifts A by `shift` bits and sets the new low bits.
+/// Paths are dropped as soon as the output stops being a suffix of the program.
+fn digit_search(program: &[usize], compiled: &CompiledProgram, shift: u32) -> Option<usize> {
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    queue.push_back(0);
+
+    while let Some(a) = queue.pop_front() {
+        for digit in 0..1 << shift {
+            let new_a = (a << shift) + digit;
+            if new_a == 0 {
+                continue;
+            }
+            let output_buffer = compiled.output(new_a, program.len());
+            if output_buffer.len() > program.len()
+                || output_buffer != program[program.len() - output_buffer.len()..] {
+                continue;
+            }
+            if output_buffer == program {
+                return Some(new_a);
+            }
+            queue.push_back(new_a);
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bits {
+    value: u64,
+    known: u64
+}
+
+impl Bits {
+    fn constant(value: u64) -> Self {
+        Bits { value, known: u64::MAX }
+    }
+
+    fn is_known(&self) -> bool {
+        self.known == u64::MAX
+    }
+
+    fn xor(self, other: Bits) -> Bits {
+        Bits { value: self.value ^ other.value, known: self.known & other.known }
+    }
+
+    fn mod8(self) -> Bits {
+        Bits { value: self.value & 7, known: self.known | !7 }
+    }
+
+    fn shr(self, shift: Bits) -> Bits {
+        if !shift.is_known() {
+            return Bits { value: 0, known: 0 };
+        }
+        if shift.value >= 64 {
+            return Bits::constant(0);
+        }
+        let shift = shift.value as u32;
+        // bits shifted in from the top are zero
+        Bits {
+            value: self.value >> shift,
+            known: (self.known >> shift) | !(u64::MAX >> shift)
+        }
+    }
+}
+
+enum Outcome {
+    Mismatch,
+    Undecided,
+    Matched,
+    // ran out of steps before anything was decided
+    TooLong
+}
+
+/// Runs the program as far as the known bits of A allow, for at most `max_steps`
+/// instructions. Also returns how many instructions were executed.
+fn partial_execute(program: &[usize], a: Bits, max_steps: usize) -> (Outcome, usize) {
+    let mut registers = [a, Bits::constant(0), Bits::constant(0)];
+    let mut instruction_pointer = 0;
+    let mut outputs = 0;
+
+    for steps in 0..max_steps {
+        let outcome = |outcome| (outcome, steps);
+        if halted(program, instruction_pointer) {
+            return outcome(if outputs == program.len() { Outcome::Matched } else { Outcome::Mismatch });
+        }
+        let (opcode, operand) = (program[instruction_pointer], program[instruction_pointer + 1]);
+        // the reserved combo operand, reached by a jump to an odd address, crashes the program
+        if uses_combo(opcode) && operand == 7 {
+            return outcome(Outcome::Mismatch);
+        }
+        let combo = || match operand {
+            0..=3 => Bits::constant(operand as u64),
+            _ => registers[operand - 4]
+        };
+
+        match opcode {
+            0 => registers[0] = registers[0].shr(combo()),
+            1 => registers[1] = registers[1].xor(Bits::constant(operand as u64)),
+            2 => registers[1] = combo().mod8(),
+            3 => {
+                let a = registers[0];
+                if a.value & a.known != 0 {
+                    instruction_pointer = operand;
+                    continue;
+                }
+                if !a.is_known() {
+                    return outcome(Outcome::Undecided);
+                }
+            },
+            4 => registers[1] = registers[1].xor(registers[2]),
+            5 => {
+                let value = combo().mod8();
+                if !value.is_known() {
+                    return outcome(Outcome::Undecided);
+                }
+                if outputs >= program.len() || value.value as usize != program[outputs] {
+                    return outcome(Outcome::Mismatch);
+                }
+                outputs += 1;
+            },
+            6 => registers[1] = registers[0].shr(combo()),
+            7 => registers[2] = registers[0].shr(combo()),
+            _ => unreachable!()
+        }
+        instruction_pointer += 2;
+    }
+    (Outcome::TooLong, max_steps)
+}
+
+/// Fix the bits of A from the least significant end, pruning assignments
+/// that already produce a wrong output. Returns the smallest quine, or an error if the
+/// search runs out of budget before it is done.
+fn constraint_search(program: &[usize], compiled: &CompiledProgram) -> Result<Option<usize>, String> {
+    let mut best = None;
+    let mut budget = SEARCH_BUDGET;
+    search(program, compiled, 0, 0, &mut best, &mut budget)?;
+    Ok(best.map(|a| a as usize))
+}
+
+fn search(
+    program: &[usize], compiled: &CompiledProgram, depth: u32, a: u64,
+    best: &mut Option<u64>, budget: &mut usize
+) -> Result<(), String> {
+    // every completion of these low bits is at least `a`
+    if best.is_some_and(|best| best <= a) {
+        return Ok(());
+    }
+    let known = if depth == 64 { u64::MAX } else { !(u64::MAX << depth) };
+    let (outcome, steps) = partial_execute(program, Bits { value: a, known }, MAX_STEPS.min(*budget));
+    *budget -= steps;
+    match outcome {
+        Outcome::Mismatch => return Ok(()),
+        // the concrete run would take at least as long
+        Outcome::TooLong => {},
+        Outcome::Undecided | Outcome::Matched => if a != 0 {
+            let (matched, steps) = compiled.matches_within(a as usize, program, *budget);
+            *budget -= steps;
+            if matched {
+                *best = Some(a);
+                return Ok(());
+            }
+        }
+    }
+    if *budget == 0 {
+        return Err(format!("Constraint search gave up after {SEARCH_BUDGET} instructions without pruning enough."));
+    }
+    if depth < 64 {
+        search(program, compiled, depth + 1, a, best, budget)?;
+        search(program, compiled, depth + 1, a | 1 << depth, best, budget)?;
+    }
+    Ok(())
+}
@@ -17,15 +17,24 @@ Part 2: from the list of coordinates, which is the first one that would
 prevent the exit being reachable?
 
 Approach: binary search
+
+Update: binary search still runs a BFS per step. Going backwards in time is cheaper: with
+all the bytes fallen, join the free neighbouring cells in a union-find, then take the bytes
+back out one by one, latest first, joining each freed cell with its free neighbours.
+The first byte whose removal connects the start and the end is the answer, in O(n α(n)).
+
+The grid size and the number of bytes for part 1 are parameters (71 and 1024 by default):
+$ cargo run example.txt --size=7 --bytes=12
+--lengths prints how the shortest path length changes as the bytes fall, and --print draws
+the part 1 path.
 */
-use std::{collections::{HashSet, VecDeque}, io, usize};
+use std::{collections::VecDeque, io};
 
-use utils::{add_checked_direction, pause, read_from_args, DIRECTIONS4};
+use utils::{add_checked_direction, get_arg, has_flag, read_from_args, DIRECTIONS4};
 
-// const T: usize = 12;
-const T: usize = 1024;
-// const SIZE: usize = 6+1;
-const SIZE: usize = 70+1;
+mod union_find;
+
+use union_find::UnionFind;
 
 fn main() -> io::Result<()> {
     let input = read_from_args()?;
@@ -34,85 +43,186 @@ fn main() -> io::Result<()> {
             let coords = line.split_once(",").unwrap();
             (coords.0.parse::<usize>().unwrap(), coords.1.parse::<usize>().unwrap())
         }).collect();
-    let coordinates: Vec<(usize, usize)> = all_coordinates.iter().take(T).cloned().collect();
+    let size: usize = get_arg("size").unwrap_or(70 + 1);
+    let bytes: usize = get_arg("bytes").unwrap_or(1024);
+    if let Some(&(x, y)) = all_coordinates.iter().find(|&&(x, y)| x >= size || y >= size) {
+        println!("Byte {x},{y} falls outside the {size}x{size} grid.");
+        return Ok(());
+    }
 
     let start = (0, 0);
-    let end = (SIZE-1, SIZE-1);
-
-    let shortest_path = find_shortest_path(&coordinates, start, end);
-    dbg!(&shortest_path.len() - 1);
-
-    // binary search
-    let mut min_i = T;
-    let mut max_i = all_coordinates.len();
-    while min_i < max_i {
-        let i = (max_i + min_i) / 2;
-        let coordinates: Vec<(usize, usize)> = all_coordinates.iter().take(i+1).cloned().collect();
-        let shortest_path = find_shortest_path(&coordinates, start, end);
-        // dbg!(min_i, max_i, i, shortest_path.len());
-        if shortest_path.len() == 0 {
-            max_i = i;
-        } else {
-            min_i = i + 1;
+    let end = (size-1, size-1);
+
+    let blocked = blocked_grid(&all_coordinates[..bytes.min(all_coordinates.len())], size);
+    let shortest_path = find_shortest_path(&blocked, start, end);
+    if has_flag("print") {
+        print_path(&blocked, &shortest_path);
+    }
+    match shortest_path.len() {
+        0 => println!("No path after {bytes} bytes."),
+        length => {
+            let shortest_path_length = length - 1;
+            dbg!(shortest_path_length);
         }
     }
-    dbg!(all_coordinates[min_i]);
-    Ok(())
-}
 
-fn find_shortest_path(coordinates: &Vec<(usize, usize)>, start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
-    let mut queue: VecDeque<((usize, usize), Vec<(usize, usize)>)> = VecDeque::new(); // (position, path)
-    let mut visited: HashSet<(usize, usize)> = HashSet::new();
-    queue.push_back((start, vec![start]));
+    if has_flag("lengths") {
+        print_lengths(&all_coordinates, size, start, end);
+    }
 
-    let mut shortest_path_size = usize::MAX;
-    let mut shortest_path: Vec<(usize, usize)> = Vec::new();
+    match first_blocking_byte(&all_coordinates, size, start, end) {
+        Some(i) => { dbg!(all_coordinates[i]); },
+        None => println!("The exit stays reachable.")
+    }
+    Ok(())
+}
 
-    while !queue.is_empty() {
-        let (position, path) = queue.pop_front().unwrap();
+/// blocked[x][y] is true if a byte fell on (x, y).
+fn blocked_grid(coordinates: &[(usize, usize)], size: usize) -> Vec<Vec<bool>> {
+    let mut blocked = vec![vec![false; size]; size];
+    for &(x, y) in coordinates {
+        blocked[x][y] = true;
+    }
+    blocked
+}
 
-        if visited.contains(&position) {
-            continue;
-        }
-        visited.insert(position);
-        
-        if coordinates.contains(&position)
-          || position.0 >= SIZE
-          || position.1 >= SIZE 
-          || path.len() >= shortest_path_size {
-            continue;
-        }
-        
-        // print_path(&coordinates, &path);
+/// BFS with the previous position of each visited one, returning the path from start to
+/// end (empty if there's none).
+fn find_shortest_path(blocked: &[Vec<bool>], start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
+    let size = blocked.len();
+    if blocked[start.0][start.1] {
+        return Vec::new();
+    }
+    let mut previous: Vec<Vec<Option<(usize, usize)>>> = vec![vec![None; size]; size];
+    let mut queue: VecDeque<(usize, usize)> = VecDeque::new();
+    previous[start.0][start.1] = Some(start);
+    queue.push_back(start);
 
+    while let Some(position) = queue.pop_front() {
         if position == end {
-            if path.len() < shortest_path_size { 
-                shortest_path_size = path.len();
-                shortest_path = path;
+            let mut path = vec![end];
+            while *path.last().unwrap() != start {
+                let (x, y) = *path.last().unwrap();
+                path.push(previous[x][y].unwrap());
             }
-            continue;
+            path.reverse();
+            return path;
         }
 
         for direction in DIRECTIONS4 {
             let Some(new_position) = add_checked_direction(position, direction) else {
                 continue;
             };
-            let mut new_path = path.clone();
-            new_path.push(new_position);
-            queue.push_back((new_position, new_path));
+            let (x, y) = new_position;
+            if x >= size || y >= size || blocked[x][y] || previous[x][y].is_some() {
+                continue;
+            }
+            previous[x][y] = Some(position);
+            queue.push_back(new_position);
+        }
+    }
+    Vec::new()
+}
+
+/// Index of the first byte after which the end can't be reached, by taking the bytes back
+/// out latest first until start and end are connected.
+fn first_blocking_byte(coordinates: &[(usize, usize)], size: usize, start: (usize, usize), end: (usize, usize)) -> Option<usize> {
+    // index of the first byte falling on each cell
+    let mut fall_time = vec![vec![usize::MAX; size]; size];
+    for (i, &(x, y)) in coordinates.iter().enumerate().rev() {
+        fall_time[x][y] = i;
+    }
+
+    let cell = |(x, y): (usize, usize)| x * size + y;
+    let mut sets = UnionFind::new(size * size);
+    let mut free = vec![vec![false; size]; size];
+    let release = |(x, y): (usize, usize), free: &mut Vec<Vec<bool>>, sets: &mut UnionFind| {
+        free[x][y] = true;
+        for direction in DIRECTIONS4 {
+            let Some((nx, ny)) = add_checked_direction((x, y), direction) else {
+                continue;
+            };
+            if nx < size && ny < size && free[nx][ny] {
+                sets.union(cell((x, y)), cell((nx, ny)));
+            }
+        }
+    };
+
+    for (x, column) in fall_time.iter().enumerate() {
+        for (y, &time) in column.iter().enumerate() {
+            if time == usize::MAX {
+                release((x, y), &mut free, &mut sets);
+            }
+        }
+    }
+    let connected = |free: &Vec<Vec<bool>>, sets: &mut UnionFind| {
+        free[start.0][start.1] && free[end.0][end.1] && sets.find(cell(start)) == sets.find(cell(end))
+    };
+    if connected(&free, &mut sets) {
+        return None;
+    }
+
+    for (i, &(x, y)) in coordinates.iter().enumerate().rev() {
+        // a cell only frees up when its first byte is taken out
+        if fall_time[x][y] != i {
+            continue;
+        }
+        release((x, y), &mut free, &mut sets);
+        if connected(&free, &mut sets) {
+            return Some(i);
         }
-        // dbg!(&queue);
     }
-    shortest_path
+    None
 }
 
-#[allow(dead_code)]
-fn print_path(coordinates: &Vec<(usize, usize)>, path: &Vec<(usize, usize)>) {
-    for j in 0..SIZE {
-        for i in 0..SIZE {
-            if coordinates.contains(&(i, j)) {
+/// Prints the shortest path length after each number of bytes fallen, as ranges where it
+/// stays the same. The BFS only reruns when a byte falls on the current path.
+fn print_lengths(coordinates: &[(usize, usize)], size: usize, start: (usize, usize), end: (usize, usize)) {
+    let mut blocked = vec![vec![false; size]; size];
+    let mut path = find_shortest_path(&blocked, start, end);
+    let mut on_path = blocked_grid(&path, size);
+    // number of bytes since which the length is the same
+    let mut since = 0;
+
+    for (i, &(x, y)) in coordinates.iter().enumerate() {
+        blocked[x][y] = true;
+        if !on_path[x][y] {
+            continue;
+        }
+        let new_path = find_shortest_path(&blocked, start, end);
+        if new_path.len() != path.len() {
+            // up to i bytes had fallen before this one
+            print_length_range(since, i, &path);
+            since = i + 1;
+        }
+        path = new_path;
+        on_path = blocked_grid(&path, size);
+        if path.is_empty() {
+            break;
+        }
+    }
+    print_length_range(since, coordinates.len(), &path);
+}
+
+fn print_length_range(from: usize, to: usize, path: &[(usize, usize)]) {
+    let length = match path.len() {
+        0 => "no path".to_string(),
+        length => (length - 1).to_string()
+    };
+    if from == to {
+        println!("{from} bytes: {length}");
+    } else {
+        println!("{from} to {to} bytes: {length}");
+    }
+}
+
+fn print_path(blocked: &[Vec<bool>], path: &[(usize, usize)]) {
+    let on_path = blocked_grid(path, blocked.len());
+    for j in 0..blocked.len() {
+        for i in 0..blocked.len() {
+            if blocked[i][j] {
                 print!("#");
-            } else if path.contains(&(i, j)) {
+            } else if on_path[i][j] {
                 print!("O");
             } else {
                 print!(".");
@@ -120,5 +230,4 @@ fn print_path(coordinates: &Vec<(usize, usize)>, path: &Vec<(usize, usize)>) {
         }
         println!();
     }
-    pause();
-}
\ No newline at end of file
+}
@@ -0,0 +1,34 @@
+/*
+Disjoint sets with path halving and union by size, so that any sequence of n operations
+takes O(n α(n)).
+*/
+pub struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>
+}
+
+impl UnionFind {
+    pub fn new(elements: usize) -> UnionFind {
+        UnionFind { parent: (0..elements).collect(), size: vec![1; elements] }
+    }
+
+    pub fn find(&mut self, mut element: usize) -> usize {
+        while self.parent[element] != element {
+            self.parent[element] = self.parent[self.parent[element]];
+            element = self.parent[element];
+        }
+        element
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            (a, b) = (b, a);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
@@ -40,15 +40,31 @@ Running time: 15.8ms
 Part 2: for each design, compute the number of different possible pattern combinations
 
 Running time: 65.3ms
+
+Trying every pattern at every suffix gets slow with many patterns, so the patterns are
+compiled into a trie (see trie.rs) and the count is done bottom-up over the positions of the
+design: ways[i] is the number of ways to make design[i..], the sum of ways[i + k] over the
+patterns of length k found at i. The same pass keeps the fewest towels needed from each
+position, and with --decompose one arrangement with the fewest towels is printed.
 */
 
-use std::collections::HashSet;
+use std::io;
 use std::time::Instant;
-use std::{collections::HashMap, io};
 
 use gcd::binary_usize;
 
-use utils::read_from_args;
+use utils::{has_flag, read_from_args};
+
+mod trie;
+
+use trie::Trie;
+
+struct Arrangements {
+    // number of ways to make the design
+    ways: usize,
+    // fewest[i]: fewest towels making design[i..], and the length of the first one
+    fewest: Vec<Option<(usize, usize)>>
+}
 
 fn main() -> io::Result<()> {
     let now = Instant::now();
@@ -56,15 +72,29 @@ fn main() -> io::Result<()> {
     let input = read_from_args()?;
     let lines = input.split_once("\n\n").unwrap();
 
-    let patterns: HashSet<&str> = lines.0.split(", ").collect();
+    let patterns: Vec<&str> = lines.0.split(", ").collect();
     let designs: Vec<&str> = lines.1.lines().collect();
+    let trie = Trie::new(&patterns);
 
-    let mut cache: HashMap<String, usize> = HashMap::new();
-
+    let mut possible_designs = 0;
     let mut num_possibilities = 0;
     for design in designs {
-        num_possibilities += compute_possibilities(design, &patterns, &mut cache);
+        let arrangements = arrange(design, &trie);
+        if arrangements.ways > 0 {
+            possible_designs += 1;
+        }
+        num_possibilities += arrangements.ways;
+
+        if has_flag("decompose") {
+            match decomposition(design, &arrangements) {
+                Some(towels) => println!(
+                    "{design}: {} ({} towels, {} ways)", towels.join(" "), towels.len(), arrangements.ways
+                ),
+                None => println!("{design}: impossible")
+            }
+        }
     }
+    dbg!(possible_designs);
     dbg!(num_possibilities);    
 
     let elapsed = now.elapsed();
@@ -73,32 +103,38 @@ fn main() -> io::Result<()> {
     Ok(())
 }
 
-fn compute_possibilities(design: &str, patterns: &HashSet<&str>, cache: &mut HashMap<String, usize>) -> usize {
-    if design.is_empty() {
-        return 1;
-    }
-
-    if let Some(&value) = cache.get(design) {
-        return value
-    }
-
-    // dbg!(&design);
-    // let mut new_patterns = patterns.clone();
-    let mut num_possibilities = 0;
-    for &pattern in patterns {
-        if pattern.len() > design.len() {
-            // new_patterns.remove(pattern);
-            continue;
-        }
-
-        if design.starts_with(pattern) {
-            num_possibilities += compute_possibilities(&design[pattern.len()..], &patterns, cache);
+fn arrange(design: &str, trie: &Trie) -> Arrangements {
+    let design = design.as_bytes();
+    let size = design.len();
+    let mut ways = vec![0; size + 1];
+    let mut fewest: Vec<Option<(usize, usize)>> = vec![None; size + 1];
+    ways[size] = 1;
+    fewest[size] = Some((0, 0));
+
+    for i in (0..size).rev() {
+        for length in trie.prefix_lengths(&design[i..]) {
+            ways[i] += ways[i + length];
+            if let Some((towels, _)) = fewest[i + length] {
+                if fewest[i].is_none_or(|(best, _)| towels + 1 < best) {
+                    fewest[i] = Some((towels + 1, length));
+                }
+            }
         }
     }
-    cache.insert(design.to_string(), num_possibilities);
-    return num_possibilities
+    Arrangements { ways: ways[0], fewest }
 }
 
+/// One arrangement with the fewest towels, following the first towel picked at each position.
+fn decomposition<'a>(design: &'a str, arrangements: &Arrangements) -> Option<Vec<&'a str>> {
+    let mut towels = Vec::new();
+    let mut i = 0;
+    while i < design.len() {
+        let (_, length) = arrangements.fewest[i]?;
+        towels.push(&design[i..i + length]);
+        i += length;
+    }
+    Some(towels)
+}
 
 #[allow(dead_code)]
 fn check_divisible(design: &str, patterns: &Vec<&str>) -> bool {
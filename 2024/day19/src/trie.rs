@@ -0,0 +1,51 @@
+/*
+Towel patterns compiled into a trie, so that all the patterns a design starts with at
+some position are found in one walk down the trie, in at most as many steps as the
+longest pattern, instead of trying every pattern there.
+*/
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct Node {
+    children: HashMap<u8, usize>,
+    // a pattern ends here
+    terminal: bool
+}
+
+pub struct Trie {
+    // the root is nodes[0]
+    nodes: Vec<Node>
+}
+
+impl Trie {
+    pub fn new(patterns: &[&str]) -> Trie {
+        let mut trie = Trie { nodes: vec![Node::default()] };
+        for pattern in patterns {
+            let mut node = 0;
+            for &color in pattern.as_bytes() {
+                node = match trie.nodes[node].children.get(&color) {
+                    Some(&child) => child,
+                    None => {
+                        trie.nodes.push(Node::default());
+                        let child = trie.nodes.len() - 1;
+                        trie.nodes[node].children.insert(color, child);
+                        child
+                    }
+                };
+            }
+            trie.nodes[node].terminal = true;
+        }
+        trie
+    }
+
+    /// Lengths of the patterns that `text` starts with, shortest first.
+    pub fn prefix_lengths<'a>(&'a self, text: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+        let mut node = 0;
+        text.iter().enumerate()
+            .map_while(move |(i, color)| {
+                node = *self.nodes[node].children.get(color)?;
+                Some((i + 1, self.nodes[node].terminal))
+            })
+            .filter_map(|(length, terminal)| terminal.then_some(length))
+    }
+}
@@ -23,16 +23,25 @@ that are within 20 movements of the cheat position.
 That is, we need to find all track positions that are within a manhattan radius = 20
 of the cheat start position. In addition, any cheat within a manhattan radius of R
 can be achieved in _ possible ways.
-*/
-use std::io;
 
-use utils::{add_direction, read_array_from_string, read_from_args, DIRECTIONS4};
+Both limits are parameters, 100 and 20 by default:
+$ cargo run input.txt --cheat=2 --min-saving=100
+and --histogram prints how many cheats save each amount of time, like the puzzle's examples:
+$ cargo run example.txt --cheat=20 --min-saving=50 --histogram
+
+The map doesn't need to be a single path either: instead of following the path, the time
+from S and the time to E are computed for every track position with a BFS from each end.
+A cheat from a to b then takes from_start[a] + |a - b| + to_end[b], and saves the
+difference with the best time without cheating.
+*/
+use std::{collections::{BTreeMap, VecDeque}, io};
 
-const MIN_TIME_SAVING: usize = 100;
-const CHEAT_SIZE: usize = 20;
+use utils::{add_checked_direction, get_arg, has_flag, read_array_from_string, read_from_args, DIRECTIONS4};
 
 fn main() -> io::Result<()> {
     let map = read_array_from_string(read_from_args().unwrap());
+    let min_time_saving: usize = get_arg("min-saving").unwrap_or(100);
+    let cheat_size: usize = get_arg("cheat").unwrap_or(20);
 
     let mut start = (0, 0);
     let mut end= (0, 0);
@@ -46,111 +55,92 @@ fn main() -> io::Result<()> {
         }
     }
 
-    let path = find_path(&map, start, end);
-    // print_path(&map, &path);
-
-    let total_time = path.len() - 1;
+    let from_start = distances(&map, start);
+    let to_end = distances(&map, end);
+    let Some(total_time) = from_start[end.0][end.1] else {
+        println!("There is no path from S to E.");
+        return Ok(());
+    };
     dbg!(total_time);
 
-    // need to check every possible cheat position along the path, as long
-    // as a > 100 ps saving is possible.
-    // That is impossible once there are fewer than 100ms remining till the end.
-    let max_cheat_time = path.len() - MIN_TIME_SAVING;
-    // each entry represents the time saving of a cheat
-    let mut cheat_savings: Vec<usize> = Vec::new();
-
-    for (t1, &cheat_position) in path[..max_cheat_time].iter().enumerate() {
-        // find directions where the cheat cuts the path
-
-        // for direction in DIRECTIONS4 {
-        //     let cheat_start = add_direction(cheat_position, direction);
-        //     if map[cheat_start.0][cheat_start.1] != '#' {
-        //         continue;
-        //     }
-        //     let cheat_end = add_direction(cheat_start, direction);
-        //     let Some(t2) = path.iter().position(|&pos| pos == cheat_end) else {
-        //         continue;
-        //     };
-
-        //     if t2 <= t1 {
-        //         continue;
-        //     }
-        //     cheat_savings.push((t2 - t1) - 2);
-        // }
+    // time saved => number of cheats
+    let mut cheat_savings: BTreeMap<usize, usize> = BTreeMap::new();
+    for (i, row) in from_start.iter().enumerate() {
+        for (j, &time) in row.iter().enumerate() {
+            let Some(t1) = time else {
+                continue;
+            };
+            // a cheat starting here can't save enough once the race is nearly over
+            if t1 + min_time_saving > total_time {
+                continue;
+            }
+            for saving in generate_cheat_savings(t1, (i, j), &to_end, total_time, cheat_size) {
+                *cheat_savings.entry(saving).or_default() += 1;
+            }
+        }
+    }
 
-        // find cheats within a 20 movement radius that cut the path
-        cheat_savings.extend(
-            generate_cheat_savings(
-                t1, cheat_position, &path
-            )
-        );
+    if has_flag("histogram") {
+        for (saving, count) in cheat_savings.range(min_time_saving.max(1)..) {
+            if *count == 1 {
+                println!("There is one cheat that saves {saving} picoseconds.");
+            } else {
+                println!("There are {count} cheats that save {saving} picoseconds.");
+            }
+        }
     }
-    dbg!(cheat_savings.iter().filter(|&val| *val >= MIN_TIME_SAVING).count());
+    let cheats: usize = cheat_savings.range(min_time_saving.max(1)..).map(|(_, count)| count).sum();
+    dbg!(cheats);
     Ok(())
 }
 
+/// Time saved by each cheat starting at `cheat_position`, reached at time t1, and ending
+/// on the track within `cheat_size` moves.
 fn generate_cheat_savings(
     t1: usize, 
     cheat_position: (usize, usize),
-    path: &Vec<(usize, usize)>, 
+    to_end: &[Vec<Option<usize>>],
+    total_time: usize,
+    cheat_size: usize
 ) -> Vec<usize> {
     let mut cheat_savings: Vec<usize> = Vec::new();
-    for (t2, &(i, j)) in path.iter().enumerate() {
-        let cheat_distance = i.abs_diff(cheat_position.0) + j.abs_diff(cheat_position.1);
-        if cheat_distance > CHEAT_SIZE {
-            continue;
-        }
-        if t2 <= t1 + cheat_distance {
-            continue;
+    let rows = cheat_position.0.saturating_sub(cheat_size)..(cheat_position.0 + cheat_size + 1).min(to_end.len());
+    for i in rows {
+        let remaining = cheat_size - i.abs_diff(cheat_position.0);
+        let columns = cheat_position.1.saturating_sub(remaining)..(cheat_position.1 + remaining + 1).min(to_end[i].len());
+        for j in columns {
+            let Some(t2) = to_end[i][j] else {
+                continue;
+            };
+            let cheat_distance = i.abs_diff(cheat_position.0) + j.abs_diff(cheat_position.1);
+            let time = t1 + cheat_distance + t2;
+            if time < total_time {
+                cheat_savings.push(total_time - time);
+            }
         }
-        cheat_savings.push((t2 - t1) - cheat_distance)
-        
     }
     cheat_savings
 }
 
-fn find_path(map: &Vec<Vec<char>>, start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
-    // DFS
-    let mut stack: Vec<(usize, usize)> = Vec::new();
-    stack.push(start);
-    // making use of the fact that there's only one path
-    // so can push every new available direction we find
-    let mut path: Vec<(usize, usize)> = Vec::new();
-    // the time is just given by the position's position in the path vector
-
-    while !stack.is_empty() {
-        let position = stack.pop().unwrap();
-
-        if path.contains(&position) {
-            continue;
-        }
-        path.push(position);
-        
-        if position == end {
-            break;
-        }
+/// BFS over the track: the number of moves from `origin` to each position, if reachable.
+fn distances(map: &[Vec<char>], origin: (usize, usize)) -> Vec<Vec<Option<usize>>> {
+    let mut distances = vec![vec![None; map[0].len()]; map.len()];
+    distances[origin.0][origin.1] = Some(0);
+    let mut queue = VecDeque::from([(origin, 0)]);
 
+    while let Some((position, distance)) = queue.pop_front() {
         for direction in DIRECTIONS4 {
-            let new_position = add_direction(position, direction);
-
-            if map[new_position.0][new_position.1] != '#' {
-                stack.push(new_position);
+            let Some(next) = add_checked_direction(position, direction) else {
+                continue;
+            };
+            if next.0 >= map.len() || next.1 >= map[next.0].len() || map[next.0][next.1] == '#' {
+                continue;
+            }
+            if distances[next.0][next.1].is_none() {
+                distances[next.0][next.1] = Some(distance + 1);
+                queue.push_back((next, distance + 1));
             }
         }
     }
-    path
-}
-
-#[allow(dead_code)]
-fn print_path(map: &Vec<Vec<char>>, path: &Vec<(usize, usize)>) {
-    for (i, row) in map.iter().enumerate() {
-        for (j, tile) in row.iter().enumerate() {
-            if path.contains(&(i, j)) {
-                print!("O");
-            } else {
-                print!("{tile}");
-            } 
-        }
-        println!();
-    }
+    distances
 }
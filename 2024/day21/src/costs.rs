@@ -0,0 +1,144 @@
+/*
+Press counts for the whole chain, computed bottom-up one keypad at a time.
+
+cost[level][a][b] is the number of human presses needed to move the arm at `level` from
+key a to key b and press it. On the keypad the human types on, that is the length of the
+shortest move. On any other keypad, each shortest move is a string of arrows typed on the
+next keypad out, starting from A, so its cost is a sum of entries of the next level's
+matrix, and cost[level][a][b] is the cheapest of them. The move picked for each pair is
+kept in choice[level], to rebuild the presses afterwards.
+
+Counts grow about 2.5 times per robot, so u128 overflows after about 90 robots.
+Past that, --modulo=M keeps the counts modulo M, which can't tell which move is cheapest
+anymore. So each level also keeps its costs as floats, divided by the level's largest
+cost so they never overflow, and the moves are picked on those instead.
+*/
+use crate::keypad::Keypad;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Count {
+    // None once it doesn't fit in a u128
+    pub exact: Option<u128>,
+    // modulo --modulo, if given
+    pub residue: u64
+}
+
+impl Count {
+    pub fn new(value: u128, modulus: Option<u64>) -> Count {
+        Count { exact: Some(value), residue: modulus.map_or(0, |m| (value % m as u128) as u64) }
+    }
+
+    pub fn add(self, other: Count, modulus: Option<u64>) -> Count {
+        Count {
+            exact: self.exact.zip(other.exact).and_then(|(a, b)| a.checked_add(b)),
+            residue: modulus.map_or(0, |m| ((self.residue as u128 + other.residue as u128) % m as u128) as u64)
+        }
+    }
+
+    pub fn scale(self, factor: u128, modulus: Option<u64>) -> Count {
+        Count {
+            exact: self.exact.and_then(|value| value.checked_mul(factor)),
+            residue: modulus.map_or(0, |m| (self.residue as u128 * (factor % m as u128) % m as u128) as u64)
+        }
+    }
+
+    pub fn format(&self, modulus: Option<u64>) -> String {
+        match (self.exact, modulus) {
+            (Some(value), _) => value.to_string(),
+            (None, Some(m)) => format!("{} (mod {m})", self.residue),
+            (None, None) => "(overflow)".to_string()
+        }
+    }
+}
+
+struct Level {
+    // indexed by the key indices of the level's keypad
+    cost: Vec<Vec<Count>>,
+    // the same costs as floats, relative to the largest one
+    relative: Vec<Vec<f64>>,
+    // index into keypad.moves of the move picked
+    choice: Vec<Vec<usize>>
+}
+
+pub struct Costs {
+    levels: Vec<Level>,
+    pub modulus: Option<u64>
+}
+
+impl Costs {
+    pub fn new(chain: &[Keypad], modulus: Option<u64>) -> Result<Costs, String> {
+        let mut costs = Costs { levels: Vec::with_capacity(chain.len()), modulus };
+
+        // built from the human's keypad inwards, and reversed at the end
+        for level in (0..chain.len()).rev() {
+            let keypad = &chain[level];
+            let size = keypad.keys.len();
+            let mut cost = vec![vec![Count::default(); size]; size];
+            let mut relative = vec![vec![0.0; size]; size];
+            let mut choice = vec![vec![0; size]; size];
+
+            for a in 0..size {
+                for b in 0..size {
+                    let candidates: Vec<(Count, f64)> = keypad.moves[a][b].iter().map(|keys| match costs.levels.last() {
+                        Some(below) => costs.typing_cost(&chain[level + 1], below, keys),
+                        None => (Count::new(keys.len() as u128, modulus), keys.len() as f64)
+                    }).collect();
+
+                    let best = if candidates.iter().all(|(count, _)| count.exact.is_some()) {
+                        (0..candidates.len()).min_by_key(|&i| candidates[i].0.exact).unwrap()
+                    } else if modulus.is_some() {
+                        (0..candidates.len()).min_by(|&i, &j| candidates[i].1.total_cmp(&candidates[j].1)).unwrap()
+                    } else {
+                        return Err(format!(
+                            "Press counts overflow u128 on keypad {level}, use --modulo=M to count modulo M."
+                        ));
+                    };
+                    (cost[a][b], relative[a][b]) = candidates[best];
+                    choice[a][b] = best;
+                }
+            }
+
+            let largest = relative.iter().flatten().fold(1.0, |largest: f64, &value| largest.max(value));
+            relative.iter_mut().flatten().for_each(|value| *value /= largest);
+            costs.levels.push(Level { cost, relative, choice });
+        }
+        costs.levels.reverse();
+        Ok(costs)
+    }
+
+    /// Human presses to type `keys` on the keypad at `level`, with its arm starting at A.
+    pub fn presses(&self, chain: &[Keypad], level: usize, keys: &[char]) -> Count {
+        self.typing_cost(&chain[level], &self.levels[level], keys).0
+    }
+
+    /// Exact and relative cost of typing `keys` on `keypad`, whose costs are in `level`.
+    fn typing_cost(&self, keypad: &Keypad, level: &Level, keys: &[char]) -> (Count, f64) {
+        let mut total = (Count::new(0, self.modulus), 0.0);
+        let mut from = keypad.index[&'A'];
+        for key in keys {
+            let to = keypad.index[key];
+            total = (total.0.add(level.cost[from][to], self.modulus), total.1 + level.relative[from][to]);
+            from = to;
+        }
+        total
+    }
+
+    /// One of the shortest human press sequences typing `keys` on the keypad at `level`,
+    /// rebuilt from the moves picked for each pair.
+    pub fn sequence(&self, chain: &[Keypad], level: usize, keys: &[char]) -> String {
+        let keypad = &chain[level];
+        let mut presses = String::new();
+        let mut from = keypad.index[&'A'];
+        for key in keys {
+            let to = keypad.index[key];
+            let path = &keypad.moves[from][to][self.levels[level].choice[from][to]];
+            if level + 1 == chain.len() {
+                presses.extend(path);
+            } else {
+                presses.push_str(&self.sequence(chain, level + 1, path));
+            }
+            from = to;
+        }
+        presses
+    }
+}
@@ -0,0 +1,201 @@
+/*
+Keypad layouts, read from a text description so that other chains can be tried
+without recompiling.
+
+Each layout is a name followed by a colon, then its rows of keys, one per line.
+A space is a gap the robot arm may not pass over, and short rows are padded with gaps.
+Layouts are separated by blank lines:
+
+numeric:
+789
+456
+123
+ 0A
+
+directional:
+ ^A
+<v>
+
+Each keypad is compiled once into a map from key to position and a table of all the
+shortest ways to move the arm between any two keys.
+*/
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use utils::{add_checked_direction, Direction, DIRECTIONS4, E, N, S, W};
+
+pub const DEFAULT_KEYPADS: &str = "numeric:\n789\n456\n123\n 0A\n\ndirectional:\n ^A\n<v>\n";
+
+#[derive(Debug, Clone)]
+pub struct Keypad {
+    pub name: String,
+    pub rows: Vec<Vec<char>>,
+    // in reading order
+    pub keys: Vec<char>,
+    // key => index in keys
+    pub index: HashMap<char, usize>,
+    pub positions: HashMap<char, (usize, usize)>,
+    // moves[from][to]: every shortest sequence of arrows between the keys, followed by A
+    pub moves: Vec<Vec<Vec<Vec<char>>>>
+}
+
+impl Keypad {
+    fn compile(name: &str, rows: Vec<Vec<char>>) -> Keypad {
+        let mut keypad = Keypad {
+            name: name.to_string(),
+            rows,
+            keys: Vec::new(),
+            index: HashMap::new(),
+            positions: HashMap::new(),
+            moves: Vec::new()
+        };
+        for (i, row) in keypad.rows.iter().enumerate() {
+            for (j, &key) in row.iter().enumerate() {
+                if key != ' ' {
+                    keypad.index.insert(key, keypad.keys.len());
+                    keypad.keys.push(key);
+                    keypad.positions.insert(key, (i, j));
+                }
+            }
+        }
+
+        let mut moves = vec![vec![Vec::new(); keypad.keys.len()]; keypad.keys.len()];
+        for (to, &key) in keypad.keys.iter().enumerate() {
+            let distances = keypad.distances(keypad.positions[&key]);
+            for (from, &start) in keypad.keys.iter().enumerate() {
+                keypad.shortest_moves(keypad.positions[&start], &distances, &mut Vec::new(), &mut moves[from][to]);
+            }
+        }
+        keypad.moves = moves;
+        keypad
+    }
+
+    pub fn contains(&self, key: char) -> bool {
+        self.index.contains_key(&key)
+    }
+
+    pub fn key_at(&self, position: (usize, usize)) -> Option<char> {
+        self.rows.get(position.0)?.get(position.1).copied().filter(|&key| key != ' ')
+    }
+
+    /// BFS distances from the given position to every key.
+    fn distances(&self, start: (usize, usize)) -> HashMap<(usize, usize), usize> {
+        let mut distances = HashMap::from([(start, 0)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(position) = queue.pop_front() {
+            for direction in DIRECTIONS4 {
+                let Some(next) = add_checked_direction(position, direction) else {
+                    continue;
+                };
+                if self.key_at(next).is_some() && !distances.contains_key(&next) {
+                    distances.insert(next, distances[&position] + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Walks down the distances to the target, collecting every shortest path.
+    fn shortest_moves(
+        &self,
+        position: (usize, usize),
+        distances: &HashMap<(usize, usize), usize>,
+        path: &mut Vec<char>,
+        moves: &mut Vec<Vec<char>>
+    ) {
+        let distance = distances[&position];
+        if distance == 0 {
+            let mut complete = path.clone();
+            complete.push('A');
+            moves.push(complete);
+            return;
+        }
+        for direction in DIRECTIONS4 {
+            let Some(next) = add_checked_direction(position, direction) else {
+                continue;
+            };
+            if distances.get(&next) == Some(&(distance - 1)) {
+                path.push(arrow(&direction));
+                self.shortest_moves(next, distances, path, moves);
+                path.pop();
+            }
+        }
+    }
+}
+
+fn arrow(d: &Direction) -> char {
+    match *d {
+        N => '^',
+        S => 'v',
+        E => '>',
+        W => '<',
+        _ => unreachable!()
+    }
+}
+
+pub fn parse_keypads(text: &str) -> Result<Vec<Keypad>, String> {
+    let text = text.replace("\r\n", "\n");
+    let mut keypads: Vec<Keypad> = Vec::new();
+    for block in text.trim_matches('\n').split("\n\n") {
+        let mut lines = block.lines();
+        let header = lines.next().unwrap_or_default();
+        let Some(name) = header.trim().strip_suffix(':') else {
+            return Err(format!("Expected a keypad name followed by a colon, found \"{header}\"."));
+        };
+        if keypads.iter().any(|keypad| keypad.name == name) {
+            return Err(format!("Keypad {name} is described twice."));
+        }
+
+        let mut rows: Vec<Vec<char>> = lines.map(|line| line.chars().collect()).collect();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if width == 0 {
+            return Err(format!("Keypad {name} has no keys."));
+        }
+        for row in rows.iter_mut() {
+            row.resize(width, ' ');
+        }
+        check_keys(name, &rows)?;
+        keypads.push(Keypad::compile(name, rows));
+    }
+    Ok(keypads)
+}
+
+/// Keys must be unique, and all reachable from A, where the arm starts.
+fn check_keys(name: &str, rows: &[Vec<char>]) -> Result<(), String> {
+    let mut keys: HashSet<char> = HashSet::new();
+    let mut start = None;
+    for (i, row) in rows.iter().enumerate() {
+        for (j, &key) in row.iter().enumerate() {
+            if key == ' ' {
+                continue;
+            }
+            if !keys.insert(key) {
+                return Err(format!("Keypad {name}: key {key} appears twice."));
+            }
+            if key == 'A' {
+                start = Some((i, j));
+            }
+        }
+    }
+    let Some(start) = start else {
+        return Err(format!("Keypad {name} has no A key."));
+    };
+
+    let mut reached = HashSet::from([start]);
+    let mut stack = vec![start];
+    while let Some(position) = stack.pop() {
+        for direction in DIRECTIONS4 {
+            let Some(next) = add_checked_direction(position, direction) else {
+                continue;
+            };
+            let key = rows.get(next.0).and_then(|row| row.get(next.1));
+            if key.is_some_and(|&key| key != ' ') && reached.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    if reached.len() < keys.len() {
+        return Err(format!("Keypad {name}: some keys can't be reached from A."));
+    }
+    Ok(())
+}
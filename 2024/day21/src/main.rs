@@ -39,143 +39,143 @@ the priority is N, E, D, W.
 This approach doesn't work. I need instead to take all the robots into account at once when doing my path search.
 So before saying that a path in the numeric keypad is the shortest, I must do a BFS on the directions keypad,
 and recurse until I get to robot C
+
+The recursion only ever asks for the cost of one key to another at a given robot, so that is
+computed bottom-up instead (see costs.rs): each keypad is compiled once into the shortest moves
+between any two keys, and the cost matrix of a robot is built from the one of the robot above it.
+Chains of thousands of robots take milliseconds, with --modulo=M once the counts overflow u128.
+
+The keypad layouts and the chain of robots are configurable at runtime (see keypad.rs):
+$ cargo run input.txt --robots=2
+$ cargo run input.txt --keypads=layouts.txt --chain=numeric,directional*3
+and --sequence prints one of the shortest press sequences for each code.
 */
 
-use std::{collections::{HashMap, VecDeque}, io, usize};
+use std::{fs, io, iter};
 
-use utils::{add_checked_direction, pause, read_from_args, Direction, DIRECTIONS4, E, N, S, W};
+use utils::{get_arg, has_flag, read_from_args};
 
-const NUMERIC_KEYPAD: &[&[char]] = &[
-    &['7', '8', '9'],
-    &['4', '5', '6'],
-    &['1', '2', '3'],
-    &[' ', '0', 'A']
-];
+mod costs;
+mod keypad;
+mod simulate;
 
-const DIRECTIONS_KEYPAD: &[&[char]] = &[
-    &[' ', '^', 'A'],
-    &['<', 'v', '>']
-];
+use costs::{Count, Costs};
+use keypad::Keypad;
 
-const MAX_DEPTH: usize = 25;
+// longest press sequence that --sequence prints and --verify replays
+const MAX_SEQUENCE_LENGTH: u128 = 1_000_000;
 
 fn main() -> io::Result<()> {
     let codes: Vec<Vec<char>> = read_from_args()?.lines().map(|l| l.chars().collect()).collect();
-    let mut cache: HashMap<(usize, char, char), usize> = HashMap::new(); // depth, from, to => length
-
-    let mut sum_complexities = 0;
-    for code in codes {
-        let mut length = 0;
-        for i in 0..code.len() {
-            let start = if i == 0 {'A'} else {code[i-1]};
-            let end = code[i];
-            length += solution(&mut cache, start, end, MAX_DEPTH);
-        }
-        println!("{}: {}", code.iter().collect::<String>(), length);
-        sum_complexities += code[..code.len()-1].iter().collect::<String>().parse::<usize>().unwrap() * length;
-    }
-    dbg!(sum_complexities);
-    Ok(())
-}
-
-fn solution(cache: &mut HashMap<(usize, char, char), usize>, from: char, to: char, depth: usize) -> usize {
-    if let Some(&length) = cache.get(&(depth, from, to)) {
-        return length;
-    }
-
-    let shortest_paths = bfs(from, to, if depth==MAX_DEPTH {NUMERIC_KEYPAD} else {DIRECTIONS_KEYPAD});
-    if depth == 0 {
-        return shortest_paths[0].len();
-    }
-
-    let mut min_length = usize::MAX;
-    for path in shortest_paths {
-        let mut length = 0;
-        for i in 0..path.len() {
-            let start = if i == 0 {'A'} else {path[i-1]};
-            let end = path[i];
-            length += solution(cache, start, end, depth-1);
+    let chain = match build_chain() {
+        Ok(chain) => chain,
+        Err(message) => {
+            println!("{message}");
+            return Ok(());
         }
-
-        if length < min_length {
-            min_length = length;
+    };
+    if let Some(presses) = get_arg::<String>("replay") {
+        match simulate::replay(&chain, &presses) {
+            Ok(typed) => println!("{typed}"),
+            Err(message) => println!("{message}")
         }
+        return Ok(());
     }
-    cache.insert((depth, from, to), min_length);
-    min_length
-}
-
-fn bfs(
-    from: char,
-    to: char,
-    keypad: &[&[char]]
-) -> Vec<Vec<char>> {
-    let mut shortest_paths: Vec<Vec<char>> = Vec::new();
-    let mut shortest_path_len= usize::MAX;
-
-    let mut queue: VecDeque<(char, Vec<char>)> = VecDeque::new();
-    queue.push_back((from, Vec::new()));
-
-    while let Some((key, mut path)) = queue.pop_front() {
-        // longer than current shortest is forbidden
-        if path.len() > shortest_path_len {
-            // because it's a BFS, any remaining paths in the queue will be longer
-            break;
+    let modulus = get_arg::<u64>("modulo");
+    let costs = match Costs::new(&chain, modulus) {
+        Ok(costs) => costs,
+        Err(message) => {
+            println!("{message}");
+            return Ok(());
         }
-            
-        if key == to {
-            if path.len() <= shortest_path_len {
-                path.push('A');
-                shortest_path_len = path.len();
-                shortest_paths.push(path);
-            }
+    };
+
+    let mut sum_complexities = Count::new(0, modulus);
+    for code in codes {
+        if let Some(key) = code.iter().find(|&&key| !chain[0].contains(key)) {
+            println!("Key {key} is not on the {} keypad.", chain[0].name);
             continue;
         }
-
-        for direction in DIRECTIONS4 {
-            let Some(new_key) = get_new_key(key, &keypad, &direction) else {
-                continue;
-            };
-
-            // gap is forbidden
-            if new_key == ' ' {
-                continue;
+        let length = costs.presses(&chain, 0, &code);
+        println!("{}: {}", code.iter().collect::<String>(), length.format(modulus));
+        if has_flag("sequence") || has_flag("verify") {
+            match length.exact {
+                Some(exact) if exact <= MAX_SEQUENCE_LENGTH => {
+                    let presses = costs.sequence(&chain, 0, &code);
+                    if has_flag("sequence") {
+                        println!("{presses}");
+                    }
+                    if has_flag("verify") {
+                        verify(&chain, &code, &presses, exact as usize);
+                    }
+                },
+                _ => println!("(sequence too long to rebuild)")
             }
-
-            let mut new_path: Vec<char> = path.clone();
-            new_path.push(map_to_char(&direction));
-            queue.push_back((new_key, new_path));    
         }
+        let numeric_part: String = code.iter().filter(|key| key.is_ascii_digit()).collect();
+        let complexity = length.scale(numeric_part.parse().unwrap_or(0), modulus);
+        sum_complexities = sum_complexities.add(complexity, modulus);
+    }
+    match sum_complexities.exact {
+        Some(sum_complexities) => { dbg!(sum_complexities); },
+        None => println!("sum_complexities = {}", sum_complexities.format(modulus))
     }
-    shortest_paths
+    Ok(())
 }
 
-fn get_new_key(key: char, keypad: &[&[char]], direction: &Direction) -> Option<char> {
-    let mut position = (0, 0);
-    'outer: for i in 0..keypad.len() {
-        for j in 0..keypad[0].len() {
-            if keypad[i][j] == key {
-                position = (i, j);
-                break 'outer;
+/// The keypads the robot arms type on, from the door outwards. By default that is the
+/// numeric keypad followed by --robots directional keypads (2 for part 1, 25 for part 2).
+/// --chain=numeric,directional*2 gives any other stack of the layouts in --keypads=file.
+fn build_chain() -> Result<Vec<Keypad>, String> {
+    let description = match get_arg::<String>("keypads") {
+        Some(path) => fs::read_to_string(&path).map_err(|error| format!("{path}: {error}"))?,
+        None => keypad::DEFAULT_KEYPADS.to_string()
+    };
+    let keypads = keypad::parse_keypads(&description)?;
+
+    let names: Vec<String> = match get_arg::<String>("chain") {
+        Some(chain) => {
+            let mut names = Vec::new();
+            for item in chain.split(',') {
+                let (name, count) = match item.split_once('*') {
+                    Some((name, count)) => (name, count.parse().map_err(|_| format!("Invalid count in {item}."))?),
+                    None => (item, 1)
+                };
+                names.extend(iter::repeat_n(name.to_string(), count));
             }
+            names
+        },
+        None => {
+            let robots = get_arg("robots").unwrap_or(25);
+            iter::once("numeric".to_string()).chain(iter::repeat_n("directional".to_string(), robots)).collect()
         }
-    }
-
-    let Some(new_position) = add_checked_direction(position, *direction) else {
-        return None;
     };
-    if new_position.0 >= keypad.len() || new_position.1 >= keypad[0].len() {
-        return None;
+
+    let mut chain = Vec::new();
+    for name in names {
+        match keypads.iter().find(|keypad| keypad.name == name) {
+            Some(keypad) => chain.push(keypad.clone()),
+            None => return Err(format!("Unknown keypad {name}."))
+        }
     }
-    Some(keypad[new_position.0][new_position.1])
+    if chain.is_empty() {
+        return Err("The chain has no keypads.".to_string());
+    }
+    // every keypad but the door's is typed on by moving the arm above it
+    for keypad in &chain[1..] {
+        if let Some(key) = "^v<>A".chars().find(|&key| !keypad.contains(key)) {
+            return Err(format!("Keypad {} is missing the {key} key to be driven by a robot.", keypad.name));
+        }
+    }
+    Ok(chain)
 }
 
-fn map_to_char(d: &Direction) -> char {
-    match *d {
-        N => '^',
-        S => 'v',
-        E => '>',
-        W => '<',
-        _ => unreachable!()
+/// Replays the rebuilt presses through the chain and checks that they type the code.
+fn verify(chain: &[Keypad], code: &[char], presses: &str, length: usize) {
+    let code: String = code.iter().collect();
+    match simulate::replay(chain, presses) {
+        Ok(typed) if typed == code && presses.len() == length => println!("verified: {length} presses type {typed}"),
+        Ok(typed) => println!("mismatch: {} presses type {typed}, expected {length} presses typing {code}", presses.len()),
+        Err(message) => println!("mismatch: {message}")
     }
-}
\ No newline at end of file
+}
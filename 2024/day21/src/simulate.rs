@@ -0,0 +1,53 @@
+/*
+Replays human presses through the whole chain of robots and returns what gets typed on
+the door's keypad, as an end to end check of the press sequences:
+
+$ cargo run input.txt --robots=2 --verify
+$ cargo run input.txt --robots=2 --replay="<vA<AA>>^AvAA<^A>A"
+
+The human's presses drive the arm of the outermost keypad. An arrow moves the arm, and
+A presses the key under it, which is passed on to the arm one keypad closer to the door.
+Moving an arm off its keypad or over a gap is an error.
+*/
+use crate::keypad::Keypad;
+
+pub fn replay(chain: &[Keypad], presses: &str) -> Result<String, String> {
+    let mut arms: Vec<(usize, usize)> = chain.iter().map(|keypad| keypad.positions[&'A']).collect();
+    let mut typed = String::new();
+
+    for (n, press) in presses.chars().enumerate() {
+        let mut level = chain.len() - 1;
+        let mut key = press;
+        loop {
+            let (i, j) = arms[level];
+            let next = match key {
+                '^' => i.checked_sub(1).map(|i| (i, j)),
+                'v' => Some((i + 1, j)),
+                '<' => j.checked_sub(1).map(|j| (i, j)),
+                '>' => Some((i, j + 1)),
+                'A' => {
+                    let pressed = chain[level].rows[i][j];
+                    if level == 0 {
+                        typed.push(pressed);
+                        break;
+                    }
+                    level -= 1;
+                    key = pressed;
+                    continue;
+                },
+                _ => return Err(format!("Press {}: {key} is not a robot command.", n + 1))
+            };
+
+            let keypad = &chain[level];
+            match next.filter(|&position| keypad.key_at(position).is_some()) {
+                Some(position) => arms[level] = position,
+                None => return Err(format!(
+                    "Press {}: the arm on keypad {level} ({}) moves {key} off the keys from {}.",
+                    n + 1, keypad.name, keypad.rows[i][j]
+                ))
+            }
+            break;
+        }
+    }
+    Ok(typed)
+}
@@ -20,46 +20,157 @@ and then go to the next seler.
 If it doesn'n see that sequence for a given banana seller, it will jump to the next one.
 
 What sequence must the monkey look for in order to maximise the number of bananas purchased?
+
+The winning sequence is printed with the price each buyer sells at, and any other sequence
+can be looked up the same way:
+$ cargo run input.txt --sequence=-2,1,-1,3
+
+The generator is linear over 24 bits, so it can also jump any number of steps at once,
+or step backwards (see matrix.rs).
+
+Approach: a price change is between -9 and 9, so the last 4 changes are a 4 digit number in
+base 19, updated in place at every step, which indexes flat arrays of totals instead of a map.
+Whether a buyer already had a pattern is kept as the buyer's number in a "seen" array, so it
+never needs clearing. The buyers are split across threads, and their totals added up at the end.
 */
-use std::{collections::{HashMap, HashSet}, io, time::Instant};
+use std::{io, thread, time::Instant};
+
+use utils::{get_arg, read_from_args};
 
-use utils::read_from_args;
+mod matrix;
+
+use matrix::Matrix;
 
 const MAX_NUMBERS: usize = 2000;
+// a price change is between -9 and 9, so 4 changes are 4 digits in base 19
+const PATTERNS: usize = 19 * 19 * 19 * 19;
 
 fn main() -> io::Result<()> {
     let time = Instant::now();
     let input = read_from_args()?;
+    let secrets: Vec<i64> = input.lines().map(|line| line.parse().unwrap()).collect();
 
-    let mut sum: i64 = 0;
-    let mut patterns_cache: HashMap<(i64, i64, i64, i64), i64> = HashMap::new(); // pattern of 4 diffs => total number of bananas
-    let mut seen: HashSet<(i64, i64, i64, i64)> = HashSet::new();
+    // each thread takes a share of the buyers, then their totals are added up
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = secrets.len().div_ceil(threads).max(1);
+    let (sum, patterns_cache) = thread::scope(|scope| {
+        let handles: Vec<_> = secrets.chunks(chunk_size)
+            .map(|chunk| scope.spawn(|| simulate(chunk)))
+            .collect();
+        handles.into_iter().fold((0, vec![0; PATTERNS]), |(sum, mut totals), handle| {
+            let (chunk_sum, chunk_totals) = handle.join().unwrap();
+            totals.iter_mut().zip(chunk_totals).for_each(|(total, chunk_total)| *total += chunk_total);
+            (sum + chunk_sum, totals)
+        })
+    });
 
-    for line in input.lines() {
-        let mut secret = line.parse::<i64>().unwrap();
-        let mut prices = [0; MAX_NUMBERS];
+    let best = (0..PATTERNS).max_by_key(|&pattern| (patterns_cache[pattern], std::cmp::Reverse(pattern))).unwrap();
+    println!("best sequence: {}", format_pattern(best));
+    report(&secrets, best);
+    dbg!(patterns_cache[best]);
+    dbg!(sum);
 
-        for price in prices.iter_mut() {
-            secret = rng(secret);
-            *price = secret % 10;
+    if let Some(steps) = get_arg::<i64>("jump") {
+        let step = if steps < 0 { Matrix::step().inverse().unwrap() } else { Matrix::step() };
+        let jump = step.pow(steps.unsigned_abs());
+        let jump_sum: i64 = secrets.iter().map(|&secret| jump.apply(secret as u32) as i64).sum();
+        let period = Matrix::step().order();
+        dbg!(period, jump_sum);
+    }
+
+    if let Some(query) = get_arg::<String>("sequence") {
+        match parse_pattern(&query) {
+            Ok(pattern) => {
+                println!("sequence {}:", format_pattern(pattern));
+                report(&secrets, pattern);
+            },
+            Err(message) => println!("{message}")
         }
+    }
+    dbg!(time.elapsed());
+    Ok(())
+}
+
+/// Evolves each secret MAX_NUMBERS times, returning the sum of the last secrets and the
+/// bananas bought with each pattern of 4 price changes, indexed in base 19.
+fn simulate(secrets: &[i64]) -> (i64, Vec<i64>) {
+    let mut sum = 0;
+    let mut patterns_cache: Vec<i64> = vec![0; PATTERNS]; // pattern of 4 diffs => total number of bananas
+    // generation stamps: seen[pattern] == buyer + 1 if that buyer already had that pattern
+    let mut seen: Vec<u32> = vec![0; PATTERNS];
+
+    for (buyer, &initial) in secrets.iter().enumerate() {
+        let stamp = buyer as u32 + 1;
+        let mut secret = initial;
+        let mut previous_price = 0;
+        let mut pattern = 0;
+
+        for i in 0..MAX_NUMBERS {
+            secret = rng(secret);
+            let price = secret % 10;
+            // shift in the latest change, dropping the oldest
+            pattern = (pattern * 19 + (price - previous_price + 9) as usize) % PATTERNS;
+            previous_price = price;
 
-        for i in 4..MAX_NUMBERS {
-            let diffs = 
-                (prices[i-3] - prices[i-4], prices[i-2] - prices[i-3], prices[i-1] - prices[i-2], prices[i] - prices[i-1]);
-            if seen.insert(diffs) {
-                *patterns_cache.entry(diffs).or_default() += prices[i];
+            if i >= 4 && seen[pattern] != stamp {
+                seen[pattern] = stamp;
+                patterns_cache[pattern] += price;
             }
         }
-        seen.clear();
         sum += secret;
     }
-    dbg!(&patterns_cache.into_values().max());
-    // let max_pattern = &patterns_cache.iter().max_by_key(|x| *x.1).unwrap();
-    // dbg!(max_pattern);
-    dbg!(sum);
-    dbg!(time.elapsed());
-    Ok(())
+    (sum, patterns_cache)
+}
+/// Prints the price each buyer sells at with the given pattern, and the totals.
+fn report(secrets: &[i64], pattern: usize) {
+    let mut buyers = 0;
+    let mut bananas = 0;
+    for &secret in secrets {
+        match sell_price(secret, pattern) {
+            Some(price) => {
+                println!("  {secret}: sells at {price}");
+                buyers += 1;
+                bananas += price;
+            },
+            None => println!("  {secret}: no sale")
+        }
+    }
+    println!("{buyers} of {} buyers sell, for {bananas} bananas", secrets.len());
+}
+
+/// The price at which the buyer sells when the monkey looks for `pattern`, if it ever appears.
+fn sell_price(initial: i64, pattern: usize) -> Option<i64> {
+    let mut secret = initial;
+    let mut previous_price = 0;
+    let mut current = 0;
+    for i in 0..MAX_NUMBERS {
+        secret = rng(secret);
+        let price = secret % 10;
+        current = (current * 19 + (price - previous_price + 9) as usize) % PATTERNS;
+        previous_price = price;
+        if i >= 4 && current == pattern {
+            return Some(price);
+        }
+    }
+    None
+}
+
+fn parse_pattern(text: &str) -> Result<usize, String> {
+    let changes: Vec<i64> = text.split(',')
+        .map(|change| change.trim().parse().map_err(|_| format!("Invalid price change {change}.")))
+        .collect::<Result<_, _>>()?;
+    if changes.len() != 4 || changes.iter().any(|change| !(-9..=9).contains(change)) {
+        return Err(format!("Expected 4 price changes between -9 and 9, got {text}."));
+    }
+    Ok(changes.iter().fold(0, |pattern, change| pattern * 19 + (change + 9) as usize))
+}
+
+fn format_pattern(pattern: usize) -> String {
+    let changes: Vec<String> = (0..4).rev()
+        .map(|digit| (pattern / 19_usize.pow(digit) % 19) as i64 - 9)
+        .map(|change| change.to_string())
+        .collect();
+    changes.join(",")
 }
 
 fn rng(s: i64) -> i64 {
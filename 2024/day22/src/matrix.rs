@@ -0,0 +1,140 @@
+/*
+The secret number generator as a matrix over GF(2).
+
+Every operation in rng is a shift or a XOR on 24 bits, so a step is a linear map on
+the 24 bits of the secret: a 24x24 bit matrix M, and N steps are M^N, which takes
+O(log N) matrix products by squaring. The matrix is invertible, which gives the step
+backwards, and its order is the period of the generator.
+
+$ cargo run input.txt --jump=1000000000000
+$ cargo run input.txt --jump=-2000
+*/
+use crate::rng;
+
+const BITS: usize = 24;
+// 2^24 - 1 = 3^2 * 5 * 7 * 13 * 17 * 241
+const MAX_PERIOD: u64 = (1 << BITS) - 1;
+const MAX_PERIOD_FACTORS: [u64; 7] = [3, 3, 5, 7, 13, 17, 241];
+
+/// Column j is the image of bit j.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix([u32; BITS]);
+
+impl Matrix {
+    pub fn identity() -> Matrix {
+        Matrix(std::array::from_fn(|j| 1 << j))
+    }
+
+    /// The matrix of one step of the generator, read off its images of each bit.
+    pub fn step() -> Matrix {
+        Matrix(std::array::from_fn(|j| rng(1 << j) as u32))
+    }
+
+    pub fn apply(&self, vector: u32) -> u32 {
+        (0..BITS).filter(|&j| vector >> j & 1 == 1).fold(0, |image, j| image ^ self.0[j])
+    }
+
+    /// self after other.
+    pub fn multiply(&self, other: &Matrix) -> Matrix {
+        Matrix(std::array::from_fn(|j| self.apply(other.0[j])))
+    }
+
+    pub fn pow(&self, mut exponent: u64) -> Matrix {
+        let mut result = Matrix::identity();
+        let mut square = *self;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&square);
+            }
+            square = square.multiply(&square);
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Gauss-Jordan elimination on the rows, None if the matrix is singular.
+    pub fn inverse(&self) -> Option<Matrix> {
+        let mut rows = self.transpose().0;
+        let mut inverse_rows = Matrix::identity().0;
+        for column in 0..BITS {
+            let pivot = (column..BITS).find(|&row| rows[row] >> column & 1 == 1)?;
+            rows.swap(column, pivot);
+            inverse_rows.swap(column, pivot);
+            for row in 0..BITS {
+                if row != column && rows[row] >> column & 1 == 1 {
+                    rows[row] ^= rows[column];
+                    inverse_rows[row] ^= inverse_rows[column];
+                }
+            }
+        }
+        Some(Matrix(inverse_rows).transpose())
+    }
+
+    fn transpose(&self) -> Matrix {
+        Matrix(std::array::from_fn(|i| {
+            (0..BITS).fold(0, |row, j| row | (self.0[j] >> i & 1) << j)
+        }))
+    }
+
+    /// Smallest n > 0 with M^n = I, if it divides 2^24 - 1 (the longest period possible
+    /// for a nonzero secret).
+    pub fn order(&self) -> Option<u64> {
+        let identity = Matrix::identity();
+        if self.pow(MAX_PERIOD) != identity {
+            return None;
+        }
+        let mut order = MAX_PERIOD;
+        for factor in MAX_PERIOD_FACTORS {
+            if self.pow(order / factor) == identity {
+                order /= factor;
+            }
+        }
+        Some(order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRETS: [i64; 6] = [1, 10, 100, 2024, 123, 16777215];
+
+    #[test]
+    fn powers_match_iterated_rng() {
+        let step = Matrix::step();
+        for secret in SECRETS {
+            let mut expected = secret;
+            for steps in 0..=2000 {
+                if [0, 1, 2, 3, 10, 100, 1000, 2000].contains(&steps) {
+                    assert_eq!(step.pow(steps).apply(secret as u32) as i64, expected, "{secret} after {steps} steps");
+                }
+                expected = rng(expected);
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_rng() {
+        let back = Matrix::step().inverse().expect("the step matrix should be invertible");
+        for secret in SECRETS {
+            assert_eq!(back.apply(rng(secret) as u32) as i64, secret);
+            assert_eq!(rng(back.apply(secret as u32) as i64), secret);
+        }
+    }
+
+    #[test]
+    fn order_divides_max_period() {
+        let period = Matrix::step().order().expect("the period should divide 2^24 - 1");
+        assert_eq!(MAX_PERIOD % period, 0);
+        // the cycle of one secret comes back within the period, and its length divides it
+        let start = SECRETS[0];
+        let mut secret = rng(start);
+        let mut length = 1;
+        while secret != start && length <= period {
+            secret = rng(secret);
+            length += 1;
+        }
+        assert_eq!(secret, start);
+        assert_eq!(period % length, 0);
+    }
+}
@@ -0,0 +1,194 @@
+/*
+Bit-parallel circuit simulator.
+
+The netlist is compiled once into an array of gates over integer wire ids, sorted
+topologically so that a single pass evaluates the whole circuit. Every wire holds a u64
+where bit k is the value of the wire in the k-th test vector, so 64 additions are
+simulated at the cost of one.
+*/
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    And,
+    Or,
+    Xor
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Gate {
+    pub op: Op,
+    pub inputs: [usize; 2],
+    pub output: usize
+}
+
+#[derive(Clone)]
+pub struct Circuit {
+    pub names: Vec<String>,
+    pub ids: HashMap<String, usize>,
+    // topologically sorted
+    pub gates: Vec<Gate>,
+    // wire ids of x00, x01, ..., y00, ... and z00, ...
+    pub x: Vec<usize>,
+    pub y: Vec<usize>,
+    pub z: Vec<usize>
+}
+
+impl Circuit {
+    pub fn compile(gates: &HashMap<&str, (&str, &str, &str)>) -> Result<Circuit, String> {
+        let mut circuit = Circuit {
+            names: Vec::new(),
+            ids: HashMap::new(),
+            gates: Vec::new(),
+            x: Vec::new(),
+            y: Vec::new(),
+            z: Vec::new()
+        };
+
+        let mut wires: Vec<&str> = gates.iter().flat_map(|(&wire, &(_, w1, w2))| [wire, w1, w2]).collect();
+        wires.sort();
+        wires.dedup();
+        for wire in wires {
+            circuit.ids.insert(wire.to_string(), circuit.names.len());
+            circuit.names.push(wire.to_string());
+        }
+        for prefix in ['x', 'y', 'z'] {
+            let bits: Vec<usize> = (0..)
+                .map_while(|bit| circuit.ids.get(&format!("{prefix}{bit:0>2}")).copied())
+                .collect();
+            match prefix {
+                'x' => circuit.x = bits,
+                'y' => circuit.y = bits,
+                _ => circuit.z = bits
+            }
+        }
+
+        for (&wire, &(op, w1, w2)) in gates {
+            let op = match op {
+                "AND" => Op::And,
+                "OR" => Op::Or,
+                "XOR" => Op::Xor,
+                _ => return Err(format!("Unknown gate {op} driving {wire}."))
+            };
+            circuit.gates.push(Gate { op, inputs: [circuit.ids[w1], circuit.ids[w2]], output: circuit.ids[wire] });
+        }
+        for gate in &circuit.gates {
+            for &input in &gate.inputs {
+                let is_input = circuit.x.contains(&input) || circuit.y.contains(&input);
+                if !is_input && !circuit.gates.iter().any(|driver| driver.output == input) {
+                    return Err(format!("{} is never driven.", circuit.names[input]));
+                }
+            }
+        }
+        circuit.sort()?;
+        Ok(circuit)
+    }
+
+    /// Kahn's algorithm, fails if the gates form a loop.
+    fn sort(&mut self) -> Result<(), String> {
+        let mut driver: Vec<Option<usize>> = vec![None; self.names.len()];
+        for (i, gate) in self.gates.iter().enumerate() {
+            driver[gate.output] = Some(i);
+        }
+        let mut pending: Vec<usize> = self.gates.iter()
+            .map(|gate| gate.inputs.iter().filter(|&&input| driver[input].is_some()).count())
+            .collect();
+        let mut consumers: Vec<Vec<usize>> = vec![Vec::new(); self.names.len()];
+        for (i, gate) in self.gates.iter().enumerate() {
+            for &input in &gate.inputs {
+                consumers[input].push(i);
+            }
+        }
+
+        let mut ready: Vec<usize> = (0..self.gates.len()).filter(|&i| pending[i] == 0).collect();
+        let mut order = Vec::with_capacity(self.gates.len());
+        while let Some(i) = ready.pop() {
+            order.push(self.gates[i]);
+            for &consumer in &consumers[self.gates[i].output] {
+                pending[consumer] -= 1;
+                if pending[consumer] == 0 {
+                    ready.push(consumer);
+                }
+            }
+        }
+        if order.len() < self.gates.len() {
+            return Err("Circuit contains a loop.".to_string());
+        }
+        self.gates = order;
+        Ok(())
+    }
+
+    /// Evaluates up to 64 test vectors at once, one per bit of the x and y lanes.
+    pub fn evaluate(&self, x: &[u64], y: &[u64]) -> Vec<u64> {
+        let mut values = vec![0_u64; self.names.len()];
+        for (&wire, &lane) in self.x.iter().zip(x).chain(self.y.iter().zip(y)) {
+            values[wire] = lane;
+        }
+        for gate in &self.gates {
+            let (a, b) = (values[gate.inputs[0]], values[gate.inputs[1]]);
+            values[gate.output] = match gate.op {
+                Op::And => a & b,
+                Op::Or => a | b,
+                Op::Xor => a ^ b
+            };
+        }
+        self.z.iter().map(|&wire| values[wire]).collect()
+    }
+
+    /// Runs the circuit on every (x, y) pair, 64 at a time.
+    pub fn add(&self, pairs: &[(usize, usize)]) -> Vec<usize> {
+        pairs.chunks(64).flat_map(|chunk| {
+            let transpose = |numbers: Vec<usize>, bits: usize| -> Vec<u64> {
+                (0..bits).map(|bit| {
+                    numbers.iter().enumerate().fold(0, |lane, (k, &number)| lane | ((number >> bit & 1) as u64) << k)
+                }).collect()
+            };
+            let x = transpose(chunk.iter().map(|&(x, _)| x).collect(), self.x.len());
+            let y = transpose(chunk.iter().map(|&(_, y)| y).collect(), self.y.len());
+            let z = self.evaluate(&x, &y);
+            (0..chunk.len()).map(move |k| {
+                z.iter().enumerate().fold(0, |number, (bit, &lane)| number | ((lane >> k & 1) as usize) << bit)
+            })
+        }).collect()
+    }
+
+    /// Exchanges the output wires of the gates driving `first` and `second`.
+    /// If that creates a loop the swap is undone and an error returned.
+    pub fn swap_outputs(&mut self, first: usize, second: usize) -> Result<(), String> {
+        let exchange = |gates: &mut Vec<Gate>| {
+            for gate in gates.iter_mut() {
+                if gate.output == first {
+                    gate.output = second;
+                } else if gate.output == second {
+                    gate.output = first;
+                }
+            }
+        };
+        exchange(&mut self.gates);
+        if let Err(message) = self.sort() {
+            exchange(&mut self.gates);
+            self.sort()?;
+            return Err(message);
+        }
+        Ok(())
+    }
+
+    pub fn driver(&self, wire: usize) -> Option<&Gate> {
+        self.gates.iter().find(|gate| gate.output == wire)
+    }
+
+    /// All the gate outputs that the given wires depend on, including themselves.
+    pub fn cone(&self, wires: &[usize]) -> HashSet<usize> {
+        let mut cone = HashSet::new();
+        let mut stack: Vec<usize> = wires.to_vec();
+        while let Some(wire) = stack.pop() {
+            let Some(gate) = self.driver(wire) else {
+                continue;
+            };
+            if cone.insert(wire) {
+                stack.extend(gate.inputs);
+            }
+        }
+        cone
+    }
+}
@@ -0,0 +1,85 @@
+/*
+Netlist export, to look at the circuit with Graphviz or check it with a Verilog simulator:
+
+$ cargo run input.txt --dot=circuit.dot && dot -Tsvg circuit.dot > circuit.svg
+$ cargo run input.txt --verilog=adder.v && iverilog adder.v
+*/
+use crate::circuit::{Circuit, Op};
+
+fn op_name(op: Op) -> &'static str {
+    match op {
+        Op::And => "AND",
+        Op::Or => "OR",
+        Op::Xor => "XOR"
+    }
+}
+
+/// Gates are nodes named after their output wire and coloured by op.
+/// Inputs are on the first rank and outputs on the last, both in bit order.
+pub fn to_dot(circuit: &Circuit) -> String {
+    let mut lines = vec!["digraph circuit {".to_string(), "    node [style=filled];".to_string()];
+
+    let inputs: Vec<&str> = circuit.x.iter().chain(&circuit.y).map(|&wire| circuit.names[wire].as_str()).collect();
+    let outputs: Vec<&str> = circuit.z.iter().map(|&wire| circuit.names[wire].as_str()).collect();
+    lines.push(format!("    {{ rank=source; {}; }}", inputs.join("; ")));
+    lines.push(format!("    {{ rank=sink; {}; }}", outputs.join("; ")));
+    for input in inputs {
+        lines.push(format!("    {input} [shape=circle, fillcolor=white];"));
+    }
+
+    for gate in &circuit.gates {
+        let wire = &circuit.names[gate.output];
+        let color = match gate.op {
+            Op::And => "lightblue",
+            Op::Or => "palegreen",
+            Op::Xor => "lightsalmon"
+        };
+        let shape = if circuit.z.contains(&gate.output) { "doubleoctagon" } else { "box" };
+        lines.push(format!("    {wire} [label=\"{wire}\\n{}\", shape={shape}, fillcolor={color}];", op_name(gate.op)));
+        for &input in &gate.inputs {
+            lines.push(format!("    {} -> {wire};", circuit.names[input]));
+        }
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/// A structural module using gate primitives, with x, y and z as buses.
+pub fn to_verilog(circuit: &Circuit, module: &str) -> String {
+    // internal wires get a prefix so they can't clash with keywords like `and` or `not`
+    let signal = |wire: usize| {
+        let name = &circuit.names[wire];
+        for (bus, wires) in [("x", &circuit.x), ("y", &circuit.y), ("z", &circuit.z)] {
+            if let Some(bit) = wires.iter().position(|&w| w == wire) {
+                return format!("{bus}[{bit}]");
+            }
+        }
+        format!("w_{name}")
+    };
+
+    let mut lines = vec![
+        format!("module {module}("),
+        format!("    input [{}:0] x,", circuit.x.len().saturating_sub(1)),
+        format!("    input [{}:0] y,", circuit.y.len().saturating_sub(1)),
+        format!("    output [{}:0] z", circuit.z.len().saturating_sub(1)),
+        ");".to_string()
+    ];
+
+    let internal: Vec<String> = circuit.gates.iter()
+        .filter(|gate| !circuit.z.contains(&gate.output))
+        .map(|gate| signal(gate.output))
+        .collect();
+    if !internal.is_empty() {
+        lines.push(format!("    wire {};", internal.join(", ")));
+    }
+    for gate in &circuit.gates {
+        let primitive = op_name(gate.op).to_lowercase();
+        let name = &circuit.names[gate.output];
+        lines.push(format!(
+            "    {primitive} g_{name}({}, {}, {});",
+            signal(gate.output), signal(gate.inputs[0]), signal(gate.inputs[1])
+        ));
+    }
+    lines.push("endmodule".to_string());
+    lines.join("\n")
+}
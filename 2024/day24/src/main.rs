@@ -25,7 +25,8 @@ z02 = 1
 which forms b100 = 4.
 
 Approach: load the x,y values into a hash table, and the logic gates
-into another hash table, and define a recursive function for the simulation
+into another hash table, then compile them into a topologically sorted list
+of gates that can be evaluated in one pass (see circuit.rs)
 
 Part 2: the circuit is trying to add numbers x and y via bitwise addition
 of x00 and y00 (least significant), x01 and y01, and so on. So it should take two
@@ -43,125 +44,104 @@ where
 c_{n+1} = (a_n AND b_n) OR (c_n AND (a_n XOR b_n))
 and c_0 = 0
 
-We know that bit 6 is wrong: it does 1 + 0 = 1 but then it does 1 + 1 = 100.
-So it's failing to add the carry correctly. It does:
-z06 = kbj XOR dhs (kbj is x06 XOR y06 so we know dhs is the carry c_6)
-z07 = x07 AND y07 (is wrong)
-when it should be
-z07 = x07 XOR y07 XOR ((x06 AND y06) OR (c6 AND (x06 XOR y06)))
-replacing the gates:
-z07 = pkm XOR (qrw OR (dhs AND kbj))
-z07 = pkm XOR (qrw OR rmq) = pkm OR cvq
-So we need to swap z07 with vmv
-
-Now onto bit 19: it does 0 + 1 = 1 but 1 + 1 = 100
-z20 = jbq OR hds (is wrong)
-hds = x20 AND y20 so should be ORed with jbq to give the carry c21
-but we have instead
-z21 = jnb XOR kfm
-as jnb = x21 OR y21, kfm is the carry so we should swap z20 with kfm
-
-Now onto bit 27. It does 0 + 1 fine but 1 + 1 = 100
-z28 = vwh AND jsg (is wrong)
-vwh = x28 XOR y28
-where we have
-hnv = jsg XOR vwh
-so we swap z28 with hnv
+The circuit is repaired automatically, see repair.rs. For my input the swapped wires were
+hnv,hth,kfm,tqr,vmv,z07,z20,z28
+*/
 
-Onto bit 35. Now it does 0 + 1 = 10 and 1 + 1 = 1.
-z36 = vvp XOR dkj = (x36 XOR y36) XOR dkj
-dkj = hth OR chh = (x35 XOR y35) OR (vkc AND tqr) = (x35 XOR y35) OR ((npt OR krf) AND (x35 AND y35))
-so looks like hth and tqr are swapped.
+use std::{collections::HashMap, fs};
 
-Result: hnv,hth,kfm,tqr,vmv,z07,z20,z28
-*/
+use utils::{get_arg, read_from_args};
 
-use std::collections::HashMap;
+mod circuit;
+mod export;
+mod repair;
+mod validate;
 
-use utils::{pause, read_from_args};
+use circuit::Circuit;
+use validate::GateLine;
 
 fn main() {
     let input = read_from_args().unwrap();
     
-    let (mut initial, mut gates) = parse_input(&input);
-    let n = initial.len() / 2 + 1;
-    let output = simulate_circuit(n, &initial, &gates);
-    dbg!(n, output);
-
-    for bit_to_check in 0..n - 1 {
-        for (x, y) in [(0, 1), (1, 1)] {
-            let (x_shifted, y_shifted) = (x << bit_to_check, y << bit_to_check);
-            fill_input(&mut initial, x_shifted, y_shifted, n);
-            let output = simulate_circuit(n, &initial, &gates);
-            let different = output != x_shifted + y_shifted;
-            println!("Bit {bit_to_check}: {x} + {y} = {:b}, {}", output >> bit_to_check, if different {"!!"} else {""});
-            if different {
-                pause();
-            }
+    let (initial, gates) = match parse_input(&input) {
+        Ok(netlist) => netlist,
+        Err(problems) => {
+            problems.iter().for_each(|problem| println!("{problem}"));
+            return;
         }
+    };
+    let circuit = match Circuit::compile(&gates) {
+        Ok(circuit) => circuit,
+        Err(message) => {
+            println!("{message}");
+            return;
+        }
+    };
+    if let Some(path) = get_arg::<String>("dot") {
+        fs::write(path, export::to_dot(&circuit)).unwrap();
     }
-
-}
-
-fn simulate_circuit(n: usize, initial: &HashMap<String, usize>, gates: &HashMap<&str, (&str, &str, &str)>) -> usize {
-    let mut output: String = String::new();
-
-    let zgates = (0..n).rev().map(|i| format!("z{i:0>2}"));
-    for gate in zgates {
-        let value = simulate(&gate, &initial, &gates);
-        output.push_str(&value.to_string());
+    if let Some(path) = get_arg::<String>("verilog") {
+        fs::write(path, export::to_verilog(&circuit, "adder")).unwrap();
+    }
+    let n = circuit.z.len();
+    match simulate_circuit(&circuit, &initial) {
+        Ok(output) => { dbg!(n, output); },
+        Err(message) => println!("{message}")
     }
-    return usize::from_str_radix(&output, 2).unwrap();
-}
 
-fn simulate(gate: &str, initial: &HashMap<String, usize>, gates: &HashMap<&str, (&str, &str, &str)>) -> usize {
-    if let Some(&value) = initial.get(gate) {
-        return value;
-    } 
-    let (op, w1, w2) = gates.get(gate).unwrap();
-    let v1 = simulate(w1, initial, gates);
-    let v2 = simulate(w2, initial, gates);
-    match *op {
-        "OR" => v1 | v2,
-        "AND" => v1 & v2,
-        "XOR" => v1 ^ v2,
-        _ => unreachable!()
+    let swaps = get_arg("swaps").unwrap_or(4);
+    match repair::repair(&circuit, swaps) {
+        Ok(wires) => println!("{}", wires.join(",")),
+        Err(message) => println!("{message}")
     }
 }
 
-/// Take an usize like 2, convert it to b10 and fill x00=0, x01=1.
-fn fill_input(initial: &mut HashMap<String, usize>, mut x: usize, mut y: usize, maxbit: usize) {
-    let mut bit = 0;
-    while bit < maxbit {
-        let (xbit, ybit) = (x % 2, y % 2);
-        let (xname, yname) = (format!("x{bit:0>2}"), format!("y{bit:0>2}"));
-        initial.insert(xname, xbit);
-        initial.insert(yname, ybit);
-        (x, y, bit) = (x / 2, y / 2, bit + 1);
-    }
+fn simulate_circuit(circuit: &Circuit, initial: &HashMap<String, usize>) -> Result<usize, String> {
+    let lanes = |wires: &[usize]| -> Result<Vec<u64>, String> {
+        wires.iter().map(|&wire| {
+            let name = &circuit.names[wire];
+            initial.get(name).map(|&value| value as u64).ok_or(format!("{name} has no initial value."))
+        }).collect()
+    };
+    let z = circuit.evaluate(&lanes(&circuit.x)?, &lanes(&circuit.y)?);
+    Ok(z.iter().rev().fold(0, |output, &lane| output << 1 | (lane & 1) as usize))
 }
 
-fn parse_input(input: &str) -> (HashMap<String, usize>, HashMap<&str, (&str, &str, &str)>) {
+fn parse_input(input: &str) -> Result<(HashMap<String, usize>, HashMap<&str, (&str, &str, &str)>), Vec<String>> {
     let mut initial: HashMap<String, usize> = HashMap::new();
-    let mut gates: HashMap<&str, (&str, &str, &str)> = HashMap::new();
+    let mut initial_lines: HashMap<&str, usize> = HashMap::new();
+    let mut gate_lines: Vec<GateLine> = Vec::new();
+    let mut problems: Vec<String> = Vec::new();
     
-    let mut lines = input.lines();
     let mut read_gates = false;
-    while let Some(line) = lines.next() {
-        if line == "" {
+    for (line_number, line) in input.lines().enumerate().map(|(i, line)| (i + 1, line)) {
+        if line.is_empty() {
             read_gates = true;
             continue;
         }
         if read_gates {
-            let (rest, wire) = line.split_once(" -> ").unwrap();
-            let mut split = rest.splitn(3, " ");
-            let (w1, op, w2) = 
-                (split.next().unwrap(), split.next().unwrap(), split.next().unwrap());
-            gates.insert(wire, (op, w1, w2));
-            continue;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts.as_slice() {
+                &[w1, op, w2, "->", wire] => gate_lines.push(GateLine { line: line_number, op, inputs: [w1, w2], output: wire }),
+                _ => problems.push(format!("line {line_number}: expected a gate like 'x00 AND y00 -> z00'"))
             }
-        let (wire, value) = line.split_once(": ").unwrap();
-        initial.insert(wire.to_owned(), value.parse::<usize>().unwrap());
+            continue;
+        }
+        match line.split_once(": ").map(|(wire, value)| (wire, value.parse::<usize>())) {
+            Some((wire, Ok(value @ (0 | 1)))) => {
+                if let Some(previous) = initial_lines.insert(wire, line_number) {
+                    problems.push(format!("line {line_number}: {wire} is already driven on line {previous}"));
+                }
+                initial.insert(wire.to_owned(), value);
+            },
+            _ => problems.push(format!("line {line_number}: expected an initial value like 'x00: 1'"))
+        }
     }
-    (initial, gates)
-}
\ No newline at end of file
+
+    problems.extend(validate::check(&initial_lines, &gate_lines));
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+    let gates = gate_lines.iter().map(|gate| (gate.output, (gate.op, gate.inputs[0], gate.inputs[1]))).collect();
+    Ok((initial, gates))
+}
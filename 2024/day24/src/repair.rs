@@ -0,0 +1,198 @@
+/*
+Automatic repair of the ripple-carry adder.
+
+Every bit n > 0 of a correct adder is built from the same five gates:
+    x_n XOR y_n -> a_n          x_n AND y_n -> b_n
+    a_n XOR c_n -> z_n          a_n AND c_n -> d_n
+    b_n OR d_n  -> c_{n+1}
+with z00 = x00 XOR y00, c_1 = x00 AND y00 and the last z being the final carry.
+
+So the wires that violate these roles are the ones that have been swapped:
+- a z wire (apart from the last one) must come from a XOR, and the last one from an OR
+- a XOR that doesn't read x/y must write a z wire
+- a XOR that reads x/y must feed another XOR (apart from bit 0)
+- an AND must feed an OR (apart from bit 0)
+
+The flagged wires are then paired up in every possible way until the circuit passes all the
+addition tests, which are run on the bit-parallel simulator in circuit.rs. If the rules don't
+single out the right wires, fall back to fixing the lowest failing bit with any swap that
+makes it pass, one swap at a time.
+*/
+use std::cmp::Reverse;
+
+use utils::XorShift;
+
+use crate::circuit::{Circuit, Op};
+
+pub fn repair(circuit: &Circuit, swaps: usize) -> Result<Vec<String>, String> {
+    check_shape(circuit)?;
+    let bits = circuit.x.len();
+    let tests = addition_tests(bits);
+    let mut circuit = circuit.clone();
+
+    let flagged = flag_wires(&circuit);
+    let names: Vec<&str> = flagged.iter().map(|&wire| circuit.names[wire].as_str()).collect();
+    println!("Wires breaking the adder structure: {}", names.join(","));
+
+    let mut swapped = Vec::new();
+    let found = if flagged.len() == 2 * swaps {
+        pair_up(&flagged, &mut swapped, &mut circuit, &tests)
+    } else {
+        false
+    };
+    if !found {
+        swapped.clear();
+        if !fix_lowest_bit(swaps, &mut swapped, &mut circuit, &tests) {
+            return Err(format!("Could not repair the adder with {swaps} swaps."));
+        }
+    }
+
+    let mut names: Vec<String> = swapped.iter().map(|&wire| circuit.names[wire].clone()).collect();
+    names.sort();
+    Ok(names)
+}
+
+/// An adder of n-bit numbers has n x wires, n y wires and n + 1 z wires.
+fn check_shape(circuit: &Circuit) -> Result<(), String> {
+    let (x, y, z) = (circuit.x.len(), circuit.y.len(), circuit.z.len());
+    if x == 0 || x != y || z != x + 1 {
+        return Err(format!("Input isn't an adder: it has {x} x, {y} y and {z} z wires."));
+    }
+    // the sums of the tests have to fit in a usize
+    if z >= usize::BITS as usize {
+        return Err(format!("Input is a {x}-bit adder, only up to {} bits are supported.", usize::BITS - 2));
+    }
+    Ok(())
+}
+
+/// Expects the shape of an adder (see check_shape).
+fn flag_wires(circuit: &Circuit) -> Vec<usize> {
+    let last_z = *circuit.z.last().unwrap();
+    let mut consumers: Vec<Vec<Op>> = vec![Vec::new(); circuit.names.len()];
+    for gate in &circuit.gates {
+        for &input in &gate.inputs {
+            consumers[input].push(gate.op);
+        }
+    }
+    let feeds = |wire: usize, op: Op| consumers[wire].contains(&op);
+    let is_input = |wire: usize| circuit.x.contains(&wire) || circuit.y.contains(&wire);
+    let is_first_bit = |wire: usize| wire == circuit.x[0] || wire == circuit.y[0];
+
+    let mut flagged: Vec<usize> = circuit.gates.iter().filter(|gate| {
+        let (wire, input) = (gate.output, gate.inputs[0]);
+        if circuit.z.contains(&wire) {
+            return if wire == last_z { gate.op != Op::Or && circuit.z.len() > 2 } else { gate.op != Op::Xor };
+        }
+        match gate.op {
+            Op::Xor if !is_input(input) => true,
+            Op::Xor => !is_first_bit(input) && !feeds(wire, Op::Xor),
+            Op::And => !is_first_bit(input) && !feeds(wire, Op::Or),
+            Op::Or => false
+        }
+    }).map(|gate| gate.output).collect();
+    flagged.sort_by_key(|&wire| &circuit.names[wire]);
+    flagged
+}
+
+/// Tries every way of splitting the wires into swapped pairs.
+fn pair_up(wires: &[usize], swapped: &mut Vec<usize>, circuit: &mut Circuit, tests: &[(usize, usize)]) -> bool {
+    if wires.is_empty() {
+        return passes(circuit, tests);
+    }
+    let first = wires[0];
+    for i in 1..wires.len() {
+        let second = wires[i];
+        if circuit.swap_outputs(first, second).is_err() {
+            continue;
+        }
+        let rest: Vec<usize> = wires[1..].iter().enumerate()
+            .filter(|&(j, _)| j + 1 != i)
+            .map(|(_, &wire)| wire)
+            .collect();
+        swapped.extend([first, second]);
+        if pair_up(&rest, swapped, circuit, tests) {
+            return true;
+        }
+        swapped.truncate(swapped.len() - 2);
+        circuit.swap_outputs(first, second).unwrap();
+    }
+    false
+}
+
+/// Finds the lowest bit that adds incorrectly and tries swaps until it's fixed.
+/// One of the two swapped wires has to feed the z wires that came out wrong, and
+/// the swaps that fix the most bits are tried first.
+fn fix_lowest_bit(swaps_left: usize, swapped: &mut Vec<usize>, circuit: &mut Circuit, tests: &[(usize, usize)]) -> bool {
+    let bit_tests: Vec<Vec<(usize, usize)>> = (0..circuit.x.len()).map(addition_tests_for_bit).collect();
+    let Some(bit) = first_failing_bit(circuit, &bit_tests, 0) else {
+        return passes(circuit, tests);
+    };
+    if swaps_left == 0 {
+        return false;
+    }
+
+    let sums = circuit.add(&bit_tests[bit]);
+    let wrong_bits = bit_tests[bit].iter().zip(sums).fold(0, |wrong, (&(x, y), sum)| wrong | (sum ^ (x + y)));
+    let wrong_outputs: Vec<usize> = (0..circuit.z.len()).filter(|i| wrong_bits >> i & 1 == 1).map(|i| circuit.z[i]).collect();
+    let mut suspects: Vec<usize> = circuit.cone(&wrong_outputs).into_iter().collect();
+    suspects.sort();
+
+    let mut wires: Vec<usize> = circuit.gates.iter().map(|gate| gate.output).collect();
+    wires.sort();
+    let tests_so_far = bit_tests[..=bit].concat();
+    let mut candidates: Vec<(usize, usize, usize)> = Vec::new();
+    for &first in &suspects {
+        for &second in &wires {
+            if first == second || circuit.swap_outputs(first, second).is_err() {
+                continue;
+            }
+            if passes(circuit, &tests_so_far) {
+                let fixed_up_to = first_failing_bit(circuit, &bit_tests, bit + 1).unwrap_or(bit_tests.len());
+                candidates.push((fixed_up_to, first, second));
+            }
+            circuit.swap_outputs(first, second).unwrap();
+        }
+    }
+    candidates.sort_by_key(|&(fixed_up_to, _, _)| Reverse(fixed_up_to));
+
+    for (_, first, second) in candidates {
+        circuit.swap_outputs(first, second).unwrap();
+        swapped.extend([first, second]);
+        if fix_lowest_bit(swaps_left - 1, swapped, circuit, tests) {
+            return true;
+        }
+        swapped.truncate(swapped.len() - 2);
+        circuit.swap_outputs(first, second).unwrap();
+    }
+    false
+}
+
+fn first_failing_bit(circuit: &Circuit, bit_tests: &[Vec<(usize, usize)>], start: usize) -> Option<usize> {
+    (start..bit_tests.len()).find(|&bit| !passes(circuit, &bit_tests[bit]))
+}
+
+fn passes(circuit: &Circuit, tests: &[(usize, usize)]) -> bool {
+    circuit.add(tests).iter().zip(tests).all(|(&sum, &(x, y))| sum == x + y)
+}
+
+/// Single bit additions with and without an incoming carry.
+fn addition_tests_for_bit(bit: usize) -> Vec<(usize, usize)> {
+    let mut tests = vec![(1 << bit, 0), (0, 1 << bit), (1 << bit, 1 << bit)];
+    if bit > 0 {
+        // carry coming from the lower bits
+        tests.push(((1 << bit) - 1, 1));
+        tests.push(((1 << bit) | ((1 << bit) - 1), 1));
+    }
+    tests
+}
+
+pub fn addition_tests(bits: usize) -> Vec<(usize, usize)> {
+    let mut tests: Vec<(usize, usize)> = (0..bits).flat_map(addition_tests_for_bit).collect();
+    // plus some pseudo-random additions
+    let mask = (1 << bits) - 1;
+    let mut random = XorShift::new(0x9e3779b97f4a7c15);
+    for _ in 0..1000 {
+        tests.push((random.next_u64() as usize & mask, random.next_u64() as usize & mask));
+    }
+    tests
+}
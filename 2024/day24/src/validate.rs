@@ -0,0 +1,101 @@
+/*
+Netlist validation, so that a broken circuit (or a bad swap) is reported instead of
+crashing the simulation. Every problem is reported with the line it comes from:
+- gates with an op other than AND, OR and XOR
+- wires driven by more than one gate, or by a gate and an initial value
+- gate inputs that nothing drives
+- combinational loops
+*/
+use std::collections::HashMap;
+
+pub struct GateLine<'a> {
+    pub line: usize,
+    pub op: &'a str,
+    pub inputs: [&'a str; 2],
+    pub output: &'a str
+}
+
+/// `initial` maps the wires with an initial value to their line number.
+pub fn check(initial: &HashMap<&str, usize>, gates: &[GateLine]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let mut drivers: HashMap<&str, usize> = initial.clone();
+    // index of the gate driving each wire
+    let mut driving_gate: HashMap<&str, usize> = HashMap::new();
+    for (i, gate) in gates.iter().enumerate() {
+        if !matches!(gate.op, "AND" | "OR" | "XOR") {
+            problems.push(format!("line {}: unknown gate {}", gate.line, gate.op));
+        }
+        match drivers.get(gate.output) {
+            Some(line) => problems.push(format!(
+                "line {}: {} is already driven on line {line}", gate.line, gate.output
+            )),
+            None => {
+                drivers.insert(gate.output, gate.line);
+                driving_gate.insert(gate.output, i);
+            }
+        }
+    }
+
+    for gate in gates {
+        for input in gate.inputs {
+            if !drivers.contains_key(input) {
+                problems.push(format!("line {}: {input} is never driven", gate.line));
+            }
+        }
+    }
+
+    for cycle in find_cycles(gates, &driving_gate) {
+        let wires: Vec<&str> = cycle.iter().map(|&i| gates[i].output).collect();
+        let lines: Vec<String> = cycle.iter().map(|&i| gates[i].line.to_string()).collect();
+        problems.push(format!(
+            "lines {}: combinational loop {} -> {}", lines.join(", "), wires.join(" -> "), wires[0]
+        ));
+    }
+    problems
+}
+
+/// Depth first search over the gates, returning the gate indices along each loop found.
+fn find_cycles(gates: &[GateLine], driving_gate: &HashMap<&str, usize>) -> Vec<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State { New, Visiting, Done }
+
+    fn visit(
+        i: usize,
+        gates: &[GateLine],
+        driving_gate: &HashMap<&str, usize>,
+        states: &mut Vec<State>,
+        path: &mut Vec<usize>,
+        cycles: &mut Vec<Vec<usize>>
+    ) {
+        states[i] = State::Visiting;
+        path.push(i);
+        for input in gates[i].inputs {
+            let Some(&j) = driving_gate.get(input) else {
+                continue;
+            };
+            match states[j] {
+                State::New => visit(j, gates, driving_gate, states, path, cycles),
+                State::Visiting => {
+                    // each gate on the path reads the next one, and i reads j
+                    let start = path.iter().position(|&k| k == j).unwrap();
+                    let mut cycle = path[start..].to_vec();
+                    cycle.reverse();
+                    cycles.push(cycle);
+                },
+                State::Done => {}
+            }
+        }
+        path.pop();
+        states[i] = State::Done;
+    }
+
+    let mut states = vec![State::New; gates.len()];
+    let mut cycles = Vec::new();
+    for i in 0..gates.len() {
+        if states[i] == State::New {
+            visit(i, gates, driving_gate, &mut states, &mut Vec::new(), &mut cycles);
+        }
+    }
+    cycles
+}
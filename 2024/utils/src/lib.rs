@@ -4,6 +4,7 @@ use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::ops::Neg;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub struct Direction {
@@ -39,6 +40,48 @@ pub fn read_from_args() -> io::Result<String> {
     fs::read_to_string(file_path)
 }
 
+/// Looks up an optional `--name=value` command line argument.
+pub fn get_arg<T: FromStr>(name: &str) -> Option<T> {
+    let prefix = format!("--{name}=");
+    env::args().skip(2).find_map(|arg| {
+        let value = arg.strip_prefix(&prefix)?;
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(_) => panic!("invalid value for --{name}: {value}")
+        }
+    })
+}
+
+/// Checks whether a `--name` flag was passed on the command line.
+pub fn has_flag(name: &str) -> bool {
+    let flag = format!("--{name}");
+    env::args().skip(2).any(|arg| arg == flag)
+}
+
+/// xorshift64 pseudo-random numbers, for reproducible randomised tests.
+pub struct XorShift {
+    state: u64
+}
+
+impl XorShift {
+    /// The seed must not be 0, which xorshift maps to itself.
+    pub fn new(seed: u64) -> XorShift {
+        XorShift { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A number in 0..bound.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
 pub fn read_array_from_string(text: String) -> Vec<Vec<char>> {
     text.lines().map(
         |line| line.chars().collect::<Vec<char>>()